@@ -0,0 +1,365 @@
+//! High-level intermediate representation of YARA files
+//!
+//! Unlike the syntax tree, the HIR is a plain owned data model without any rowan nodes.
+//! Literals are decoded (escape sequences in strings, hex bytes, integer suffixes),
+//! trivia is dropped and every item only keeps a `TextRange` span pointing back to the source.
+//! This makes it easy to pass it to other threads, store it or serialize it
+//! (with the `serde` feature enabled).
+//!
+//! The HIR is created by lowering the syntax tree with `lower`. Parts of the tree
+//! that cannot be lowered (because of syntax errors or invalid literals) are skipped
+//! and reported as errors.
+
+mod lower;
+
+use text_size::TextRange;
+
+pub use self::lower::lower;
+pub(crate) use self::lower::{lower_meta, lower_pattern_modifier, parse_int, unescape};
+pub use crate::syntax::ast::{BinaryOp, BoolTermExprOp, ExprOp, LogicOp, UnaryOp};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceFile {
+    pub imports: Vec<Import>,
+    pub includes: Vec<Include>,
+    pub rules: Vec<Rule>,
+}
+
+/// `import "module"`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Import {
+    pub module: String,
+    pub span: TextRange,
+}
+
+/// `include "path"`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Include {
+    pub path: String,
+    pub span: TextRange,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rule {
+    pub name: String,
+    pub modifiers: Vec<RuleModifier>,
+    pub tags: Vec<String>,
+    pub meta: Vec<Meta>,
+    pub patterns: Vec<Pattern>,
+    /// `None` if the condition is missing or it could not be lowered
+    pub condition: Option<Expr>,
+    pub span: TextRange,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RuleModifier {
+    Private,
+    Global,
+}
+
+/// `key = value` in the `meta` section
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Meta {
+    pub key: String,
+    pub value: MetaValue,
+    pub span: TextRange,
+}
+
+/// Metadata of a rule in source order
+///
+/// Unlike a map, it keeps all entries with duplicate keys.
+/// Lookups by key return the first entry with the key.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    entries: Vec<Meta>,
+}
+
+impl Metadata {
+    pub fn new(entries: Vec<Meta>) -> Self {
+        Metadata { entries }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Meta> {
+        self.entries.iter().find(|it| it.key == key)
+    }
+
+    /// All entries with the key in source order
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Meta> + 'a {
+        self.entries.iter().filter(move |it| it.key == key)
+    }
+
+    /// Value of the first entry with the key, if it is a valid UTF-8 string
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        std::str::from_utf8(self.get_bytes(key)?).ok()
+    }
+
+    /// Value of the first entry with the key, if it is a string
+    pub fn get_bytes(&self, key: &str) -> Option<&[u8]> {
+        match &self.get(key)?.value {
+            MetaValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Value of the first entry with the key, if it is an integer
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        match self.get(key)?.value {
+            MetaValue::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Value of the first entry with the key, if it is a float
+    pub fn get_float(&self, key: &str) -> Option<f64> {
+        match self.get(key)?.value {
+            MetaValue::Float(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Value of the first entry with the key, if it is a bool
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)?.value {
+            MetaValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Meta> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Metadata {
+    type Item = &'a Meta;
+    type IntoIter = std::slice::Iter<'a, Meta>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Metadata {
+    type Item = Meta;
+    type IntoIter = std::vec::IntoIter<Meta>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MetaValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// Unescaped bytes, the string does not have to be valid UTF-8 like `"\xff"`
+    String(Vec<u8>),
+}
+
+/// Pattern declared in the `strings` section
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pattern {
+    /// Name without the `$`, empty for anonymous patterns
+    pub name: String,
+    pub kind: PatternKind,
+    pub modifiers: Vec<PatternModifier>,
+    pub span: TextRange,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PatternKind {
+    /// Text string with decoded escape sequences
+    Text(Vec<u8>),
+    Hex(Vec<HexToken>),
+    Regex(Regex),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HexToken {
+    /// Byte that matches if `input & mask == value & mask`,
+    /// `negated` bytes (`~XX`) match if it does not
+    Byte { value: u8, mask: u8, negated: bool },
+    /// `[from-to]`, missing bounds are unbounded
+    Jump { from: Option<u64>, to: Option<u64> },
+    /// `( alternative | alternative )`
+    Alternative(Vec<Vec<HexToken>>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Regex {
+    /// Regular expression without the slashes
+    pub pattern: String,
+    pub case_insensitive: bool,
+    pub dot_matches_all: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PatternModifier {
+    Ascii,
+    Wide,
+    Nocase,
+    Private,
+    Fullword,
+    /// `xor` or `xor(from-to)`, a single key is represented as the same bounds
+    Xor {
+        from: u8,
+        to: u8,
+    },
+    /// `base64` with optional custom alphabet
+    Base64(Option<Vec<u8>>),
+    /// `base64wide` with optional custom alphabet
+    Base64Wide(Option<Vec<u8>>),
+}
+
+/// Expression in the condition
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: TextRange,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExprKind {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(Vec<u8>),
+    Regex(Regex),
+    Filesize,
+    Entrypoint,
+    Ident(String),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    /// `$a`, the name is without the `$` and empty for the anonymous pattern
+    PatternMatch {
+        pattern: String,
+        anchor: Option<Anchor>,
+    },
+    /// `#a`
+    Count {
+        pattern: String,
+        range: Option<Box<Range>>,
+    },
+    /// `@a`
+    Offset {
+        pattern: String,
+        index: Option<Box<Expr>>,
+    },
+    /// `!a`
+    Length {
+        pattern: String,
+        index: Option<Box<Expr>>,
+    },
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    Index {
+        base: Box<Expr>,
+        index: Box<Expr>,
+    },
+    FieldAccess {
+        base: Box<Expr>,
+        field: String,
+    },
+    Of {
+        quantifier: Quantifier,
+        set: OfSet,
+        anchor: Option<Anchor>,
+    },
+    For {
+        quantifier: Quantifier,
+        iteration: ForIteration,
+        body: Box<Expr>,
+    },
+}
+
+/// `( lower .. upper )`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Range {
+    pub lower: Expr,
+    pub upper: Expr,
+    pub span: TextRange,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Anchor {
+    At(Box<Expr>),
+    In(Box<Range>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Quantifier {
+    All,
+    Any,
+    None,
+    Number(Box<Expr>),
+    Percent(Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OfSet {
+    Them,
+    Patterns(Vec<PatternSet>),
+    Exprs(Vec<Expr>),
+}
+
+/// `$a` or `$a*` in a pattern tuple
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternSet {
+    /// Name or prefix without the `$`
+    pub name: String,
+    pub wildcard: bool,
+    pub span: TextRange,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ForIteration {
+    Patterns(OfSet),
+    Iterable { vars: Vec<String>, iterable: Iterable },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Iterable {
+    Range(Box<Range>),
+    Tuple(Vec<Expr>),
+    Expr(Box<Expr>),
+}
@@ -0,0 +1,717 @@
+//! Lowering of the typed syntax tree into the HIR
+//!
+//! Every item is lowered on its own, so an error in one pattern or metadata
+//! does not prevent the rest of the rule from being lowered.
+//! Items that cannot be lowered are skipped and an error is reported for them,
+//! if there is no more specific error (like an invalid escape sequence) an
+//! "incomplete ..." error with the span of the whole item is used.
+
+use text_size::TextRange;
+
+use crate::{
+    hir::{
+        Anchor, Expr, ExprKind, ForIteration, HexToken, Import, Include, Iterable, Meta, MetaValue,
+        OfSet, Pattern, PatternKind, PatternModifier, PatternSet, Quantifier, Range, Regex, Rule,
+        RuleModifier, SourceFile,
+    },
+    syntax::ast::{self, AstNode, Cond, CondRange, IterableKind, LiteralKind, QuantifierKind},
+    SyntaxError, SyntaxKind, SyntaxNode, SyntaxToken,
+};
+
+/// Lower the syntax tree of a file into the HIR
+/// Returns the lowered file and errors for the parts that could not be lowered
+pub fn lower(file: &ast::SourceFile) -> (SourceFile, Vec<SyntaxError>) {
+    let mut ctx = LowerCtx::default();
+    let file = ctx.source_file(file);
+    (file, ctx.errors)
+}
+
+/// Lower a single metadata statement, `None` if the value is missing or invalid
+pub(crate) fn lower_meta(stmt: &ast::MetaStmt) -> Option<Meta> {
+    LowerCtx::default().meta(stmt)
+}
+
+/// Lower a single pattern modifier, `None` if it is incomplete or invalid
+pub(crate) fn lower_pattern_modifier(modifier: &ast::PatternMod) -> Option<PatternModifier> {
+    LowerCtx::default().pattern_modifier(modifier)
+}
+
+#[derive(Default)]
+struct LowerCtx {
+    errors: Vec<SyntaxError>,
+}
+
+impl LowerCtx {
+    fn error(&mut self, message: impl Into<String>, range: TextRange) {
+        self.errors.push(SyntaxError::new(message, range));
+    }
+
+    /// Lower the item with `f` and report it as incomplete if it fails
+    /// without a more specific error
+    fn item<T>(
+        &mut self,
+        what: &str,
+        node: &SyntaxNode,
+        f: impl FnOnce(&mut Self) -> Option<T>,
+    ) -> Option<T> {
+        let n_errors = self.errors.len();
+        let res = f(self);
+        if res.is_none() && self.errors.len() == n_errors {
+            self.error(format!("incomplete {}", what), span(node));
+        }
+        res
+    }
+
+    fn source_file(&mut self, file: &ast::SourceFile) -> SourceFile {
+        let imports = file
+            .import_stmts()
+            .filter_map(|import| {
+                self.item("import", import.syntax(), |ctx| {
+                    let module = ctx.utf8_string(&import.string_lit_token()?)?;
+                    Some(Import { module, span: span(import.syntax()) })
+                })
+            })
+            .collect();
+        let includes = file
+            .include_stmts()
+            .filter_map(|include| {
+                self.item("include", include.syntax(), |ctx| {
+                    let path = ctx.utf8_string(&include.string_lit_token()?)?;
+                    Some(Include { path, span: span(include.syntax()) })
+                })
+            })
+            .collect();
+        let rules = file
+            .rules()
+            .filter_map(|rule| self.item("rule", rule.syntax(), |ctx| ctx.rule(&rule)))
+            .collect();
+        SourceFile { imports, includes, rules }
+    }
+
+    fn rule(&mut self, rule: &ast::Rule) -> Option<Rule> {
+        let name = rule.identifier_token()?.text().to_string();
+        let modifiers = rule
+            .modifiers()
+            .filter_map(|it| match (it.private_token(), it.global_token()) {
+                (Some(_), _) => Some(RuleModifier::Private),
+                (_, Some(_)) => Some(RuleModifier::Global),
+                _ => None,
+            })
+            .collect();
+        let tags = rule
+            .tags()
+            .filter_map(|it| it.identifier_token())
+            .map(|it| it.text().to_string())
+            .collect();
+        let body = rule.body()?;
+        let meta = body
+            .meta()
+            .into_iter()
+            .flat_map(|it| it.meta_stmts())
+            .filter_map(|stmt| self.item("metadata", stmt.syntax(), |ctx| ctx.meta(&stmt)))
+            .collect();
+        let patterns = body
+            .strings()
+            .into_iter()
+            .flat_map(|it| it.variable_stmts())
+            .filter_map(|stmt| self.item("pattern", stmt.syntax(), |ctx| ctx.pattern(&stmt)))
+            .collect();
+        let condition = body.condition().and_then(|condition| {
+            self.item("condition", condition.syntax(), |ctx| ctx.expr(&condition.cond()?))
+        });
+        Some(Rule { name, modifiers, tags, meta, patterns, condition, span: span(rule.syntax()) })
+    }
+
+    fn meta(&mut self, stmt: &ast::MetaStmt) -> Option<Meta> {
+        let key = stmt.identifier_token()?.text().to_string();
+        let value = if let Some(token) = stmt.bool_lit_token() {
+            MetaValue::Bool(token.text() == "true")
+        } else if let Some(token) = stmt.string_lit_token() {
+            MetaValue::String(self.string(&token)?)
+        } else if let Some(token) = stmt.int_lit_token() {
+            MetaValue::Int(self.int(&token)?)
+        } else {
+            MetaValue::Float(self.float(&stmt.float_lit_token()?)?)
+        };
+        Some(Meta { key, value, span: span(stmt.syntax()) })
+    }
+
+    fn pattern(&mut self, stmt: &ast::VariableStmt) -> Option<Pattern> {
+        let name = stmt.variable_token()?.text()[1..].to_string();
+        let pattern = stmt.pattern()?;
+        let kind = if let Some(token) = pattern.string_lit_token() {
+            PatternKind::Text(self.string(&token)?)
+        } else if let Some(hex) = pattern.hex_pattern() {
+            PatternKind::Hex(self.hex_token(&hex.hex_token()?)?)
+        } else {
+            PatternKind::Regex(self.regex(&pattern.regex_pattern()?)?)
+        };
+        let modifiers = pattern
+            .pattern_mods()
+            .map(|it| self.pattern_modifier(&it))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect::<Option<_>>()?;
+        Some(Pattern { name, kind, modifiers, span: span(stmt.syntax()) })
+    }
+
+    fn pattern_modifier(&mut self, modifier: &ast::PatternMod) -> Option<PatternModifier> {
+        let alphabet = |ctx: &mut Self| match modifier.base_alphabet() {
+            Some(alphabet) => {
+                let token = alphabet.string_lit_token()?;
+                let value = ctx.string(&token)?;
+                if value.len() != 64 {
+                    ctx.error("base64 alphabet must be 64 bytes long", token.text_range());
+                    return None;
+                }
+                Some(Some(value))
+            }
+            None => Some(None),
+        };
+        let res = match first_token(modifier.syntax())?.kind() {
+            SyntaxKind::ASCII_KW => PatternModifier::Ascii,
+            SyntaxKind::WIDE_KW => PatternModifier::Wide,
+            SyntaxKind::NOCASE_KW => PatternModifier::Nocase,
+            SyntaxKind::PRIVATE_KW => PatternModifier::Private,
+            SyntaxKind::FULLWORD_KW => PatternModifier::Fullword,
+            SyntaxKind::BASE64_KW => PatternModifier::Base64(alphabet(self)?),
+            SyntaxKind::BASE64WIDE_KW => PatternModifier::Base64Wide(alphabet(self)?),
+            SyntaxKind::XOR_KW => match modifier.xor_range() {
+                Some(range) => self.xor_range(&range)?,
+                None => PatternModifier::Xor { from: 0, to: 255 },
+            },
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    fn xor_range(&mut self, range: &ast::XorRange) -> Option<PatternModifier> {
+        let mut keys = Vec::new();
+        for token in tokens(range.syntax()).filter(|it| it.kind() == SyntaxKind::INT_LIT) {
+            match self.int(&token).map(u8::try_from) {
+                Some(Ok(key)) => keys.push(key),
+                Some(Err(_)) => {
+                    self.error("xor key must be in range 0-255", token.text_range());
+                    return None;
+                }
+                None => return None,
+            }
+        }
+        let (from, to) = match keys[..] {
+            [key] => (key, key),
+            [from, to] => (from, to),
+            _ => return None,
+        };
+        if from > to {
+            self.error("lower bound of xor range exceeds upper bound", span(range.syntax()));
+            return None;
+        }
+        Some(PatternModifier::Xor { from, to })
+    }
+
+    fn hex_token(&mut self, token: &ast::HexToken) -> Option<Vec<HexToken>> {
+        let mut res = vec![self.hex_item(token.hex_byte(), token.hex_alternative())?];
+        for tail in token.hex_token_tails() {
+            for jump in tail.hex_jumps() {
+                res.push(self.hex_jump(&jump)?);
+            }
+            res.push(self.hex_item(tail.hex_byte(), tail.hex_alternative())?);
+        }
+        Some(res)
+    }
+
+    fn hex_item(
+        &mut self,
+        byte: Option<ast::HexByte>,
+        alternative: Option<ast::HexAlternative>,
+    ) -> Option<HexToken> {
+        if let Some(byte) = byte {
+            let token = byte.hex_lit_token()?;
+            return match parse_hex_byte(token.text()) {
+                Some(byte) => Some(byte),
+                None => {
+                    self.error(format!("invalid hex byte `{}`", token.text()), token.text_range());
+                    None
+                }
+            };
+        }
+        let alternative = alternative?;
+        let mut alternatives = vec![self.hex_token(&alternative.hex_token()?)?];
+        for pipe in alternative.hex_pipes() {
+            alternatives.push(self.hex_token(&pipe.hex_token()?)?);
+        }
+        Some(HexToken::Alternative(alternatives))
+    }
+
+    fn hex_jump(&mut self, jump: &ast::HexJump) -> Option<HexToken> {
+        let mut from = None;
+        let mut to = None;
+        let mut after_hyphen = false;
+        for token in tokens(jump.syntax()) {
+            match token.kind() {
+                SyntaxKind::HYPHEN => after_hyphen = true,
+                SyntaxKind::INT_LIT => {
+                    let value = u64::try_from(self.int(&token)?).ok()?;
+                    *(if after_hyphen { &mut to } else { &mut from }) = Some(value);
+                }
+                _ => (),
+            }
+        }
+        if !after_hyphen {
+            to = from;
+        }
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                self.error("lower bound of jump exceeds upper bound", span(jump.syntax()));
+                return None;
+            }
+        }
+        Some(HexToken::Jump { from, to })
+    }
+
+    fn regex(&mut self, regex: &ast::RegexPattern) -> Option<Regex> {
+        let pattern = regex.regex_lit_token()?.text().to_string();
+        let mut res = Regex { pattern, case_insensitive: false, dot_matches_all: false };
+        for modifier in regex.regex_mods() {
+            res.case_insensitive |= modifier.case_insensitive_token().is_some();
+            res.dot_matches_all |= modifier.dot_matches_all_token().is_some();
+        }
+        Some(res)
+    }
+
+    fn expr(&mut self, cond: &Cond) -> Option<Expr> {
+        let kind = match cond {
+            Cond::Binary { op, lhs, rhs, .. } => {
+                let (lhs, rhs) = (self.expr(lhs), self.expr(rhs));
+                ExprKind::Binary { op: *op, lhs: Box::new(lhs?), rhs: Box::new(rhs?) }
+            }
+            Cond::Unary { op, operand, .. } => {
+                ExprKind::Unary { op: *op, operand: Box::new(self.expr(operand)?) }
+            }
+            Cond::Literal { syntax, token, kind } => match kind {
+                LiteralKind::Bool => ExprKind::Bool(token.text() == "true"),
+                LiteralKind::Int => ExprKind::Int(self.int(token)?),
+                LiteralKind::Float => ExprKind::Float(self.float(token)?),
+                LiteralKind::String => ExprKind::String(self.string(token)?),
+                LiteralKind::Regex => {
+                    ExprKind::Regex(self.regex(&ast::RegexPattern::cast(syntax.clone())?)?)
+                }
+                LiteralKind::Filesize => ExprKind::Filesize,
+                LiteralKind::Entrypoint => ExprKind::Entrypoint,
+            },
+            Cond::Ident { name, .. } => ExprKind::Ident(name.text().to_string()),
+            Cond::PatternMatch { pattern, anchor, .. } => {
+                let anchor = match anchor {
+                    Some(anchor) => Some(self.anchor(anchor)?),
+                    None => None,
+                };
+                ExprKind::PatternMatch { pattern: pattern_name(pattern), anchor }
+            }
+            Cond::Count { pattern, range, .. } => {
+                let range = match range {
+                    Some(range) => Some(Box::new(self.range(range)?)),
+                    None => None,
+                };
+                ExprKind::Count { pattern: pattern_name(pattern), range }
+            }
+            Cond::Offset { pattern, index, .. } => {
+                let index = self.optional_expr(index.as_deref())?;
+                ExprKind::Offset { pattern: pattern_name(pattern), index }
+            }
+            Cond::Length { pattern, index, .. } => {
+                let index = self.optional_expr(index.as_deref())?;
+                ExprKind::Length { pattern: pattern_name(pattern), index }
+            }
+            Cond::Call { callee, args, .. } => {
+                ExprKind::Call { callee: Box::new(self.expr(callee)?), args: self.exprs(args)? }
+            }
+            Cond::Index { base, index, .. } => {
+                let (base, index) = (self.expr(base), self.expr(index));
+                ExprKind::Index { base: Box::new(base?), index: Box::new(index?) }
+            }
+            Cond::FieldAccess { base, field, .. } => ExprKind::FieldAccess {
+                base: Box::new(self.expr(base)?),
+                field: field.text().to_string(),
+            },
+            Cond::Of { quantifier, set, anchor, .. } => {
+                let anchor = match anchor {
+                    Some(anchor) => Some(self.anchor(anchor)?),
+                    None => None,
+                };
+                ExprKind::Of {
+                    quantifier: self.quantifier(quantifier)?,
+                    set: self.of_set(set)?,
+                    anchor,
+                }
+            }
+            Cond::For { quantifier, iteration, body, .. } => {
+                let iteration = match iteration {
+                    ast::ForIteration::Patterns(set) => ForIteration::Patterns(self.of_set(set)?),
+                    ast::ForIteration::Iterable { vars, iterable } => ForIteration::Iterable {
+                        vars: vars.iter().map(|it| it.text().to_string()).collect(),
+                        iterable: self.iterable(iterable)?,
+                    },
+                };
+                ExprKind::For {
+                    quantifier: self.quantifier(quantifier)?,
+                    iteration,
+                    body: Box::new(self.expr(body)?),
+                }
+            }
+            Cond::Paren { inner, .. } => return self.expr(inner),
+            Cond::Error { syntax } => {
+                self.error("incomplete expression", span(syntax));
+                return None;
+            }
+        };
+        Some(Expr { kind, span: span(cond.syntax()) })
+    }
+
+    /// Lower all expressions, errors are reported for each of them
+    fn exprs(&mut self, conds: &[Cond]) -> Option<Vec<Expr>> {
+        conds.iter().map(|it| self.expr(it)).collect::<Vec<_>>().into_iter().collect()
+    }
+
+    fn optional_expr(&mut self, cond: Option<&Cond>) -> Option<Option<Box<Expr>>> {
+        match cond {
+            Some(cond) => Some(Some(Box::new(self.expr(cond)?))),
+            None => Some(None),
+        }
+    }
+
+    fn range(&mut self, range: &CondRange) -> Option<Range> {
+        let (lower, upper) = (self.expr(&range.lower), self.expr(&range.upper));
+        Some(Range { lower: lower?, upper: upper?, span: span(&range.syntax) })
+    }
+
+    fn anchor(&mut self, anchor: &ast::Anchor) -> Option<Anchor> {
+        match anchor {
+            ast::Anchor::At(offset) => Some(Anchor::At(Box::new(self.expr(offset)?))),
+            ast::Anchor::In(range) => Some(Anchor::In(Box::new(self.range(range)?))),
+        }
+    }
+
+    fn quantifier(&mut self, quantifier: &QuantifierKind) -> Option<Quantifier> {
+        let res = match quantifier {
+            QuantifierKind::All => Quantifier::All,
+            QuantifierKind::Any => Quantifier::Any,
+            QuantifierKind::None => Quantifier::None,
+            QuantifierKind::Number(n) => Quantifier::Number(Box::new(self.expr(n)?)),
+            QuantifierKind::Percent(n) => Quantifier::Percent(Box::new(self.expr(n)?)),
+        };
+        Some(res)
+    }
+
+    fn of_set(&mut self, set: &ast::OfSet) -> Option<OfSet> {
+        let res = match set {
+            ast::OfSet::Them => OfSet::Them,
+            ast::OfSet::Patterns(patterns) => OfSet::Patterns(
+                patterns
+                    .iter()
+                    .map(|it| {
+                        Some(PatternSet {
+                            name: pattern_name(&it.variable_token()?),
+                            wildcard: it.star_token().is_some(),
+                            span: span(it.syntax()),
+                        })
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            ast::OfSet::Conds(conds) => OfSet::Exprs(self.exprs(conds)?),
+        };
+        Some(res)
+    }
+
+    fn iterable(&mut self, iterable: &IterableKind) -> Option<Iterable> {
+        let res = match iterable {
+            IterableKind::Range(range) => Iterable::Range(Box::new(self.range(range)?)),
+            IterableKind::Tuple(conds) => Iterable::Tuple(self.exprs(conds)?),
+            IterableKind::Expr(expr) => Iterable::Expr(Box::new(self.expr(expr)?)),
+        };
+        Some(res)
+    }
+
+    fn int(&mut self, token: &SyntaxToken) -> Option<i64> {
+        let res = parse_int(token.text());
+        if res.is_none() {
+            self.error(format!("invalid integer `{}`", token.text()), token.text_range());
+        }
+        res
+    }
+
+    fn float(&mut self, token: &SyntaxToken) -> Option<f64> {
+        let res = token.text().parse().ok();
+        if res.is_none() {
+            self.error(format!("invalid float `{}`", token.text()), token.text_range());
+        }
+        res
+    }
+
+    fn string(&mut self, token: &SyntaxToken) -> Option<Vec<u8>> {
+        match unescape(token.text()) {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.error(message, token.text_range());
+                None
+            }
+        }
+    }
+
+    fn utf8_string(&mut self, token: &SyntaxToken) -> Option<String> {
+        let res = String::from_utf8(self.string(token)?).ok();
+        if res.is_none() {
+            self.error("string is not valid UTF-8", token.text_range());
+        }
+        res
+    }
+}
+
+/// Range of the node without leading and trailing trivia
+fn span(node: &SyntaxNode) -> TextRange {
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !it.kind().is_trivia());
+    match tokens.next() {
+        Some(first) => {
+            let end = tokens.last().unwrap_or_else(|| first.clone()).text_range().end();
+            TextRange::new(first.text_range().start(), end)
+        }
+        None => node.text_range(),
+    }
+}
+
+fn tokens(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    node.children_with_tokens().filter_map(|it| it.into_token()).filter(|it| !it.kind().is_trivia())
+}
+
+fn first_token(node: &SyntaxNode) -> Option<SyntaxToken> {
+    tokens(node).next()
+}
+
+/// Name of the referenced pattern without the `$`, `#`, `@` or `!`
+fn pattern_name(token: &SyntaxToken) -> String {
+    token.text()[1..].to_string()
+}
+
+/// Parse decimal, hexadecimal (`0x`) and octal (`0o`) integers
+/// with optional `KB` or `MB` suffix
+pub(crate) fn parse_int(text: &str) -> Option<i64> {
+    if let Some(hex) = text.strip_prefix("0x") {
+        return i64::from_str_radix(hex, 16).ok();
+    }
+    if let Some(oct) = text.strip_prefix("0o") {
+        return i64::from_str_radix(oct, 8).ok();
+    }
+    let (number, multiplier) = match (text.strip_suffix("KB"), text.strip_suffix("MB")) {
+        (Some(number), _) => (number, 1024),
+        (_, Some(number)) => (number, 1024 * 1024),
+        _ => (text, 1),
+    };
+    number.parse::<i64>().ok()?.checked_mul(multiplier)
+}
+
+/// Parse a hex byte like `4D`, `?D`, `4?`, `??` or `~4D`
+fn parse_hex_byte(text: &str) -> Option<HexToken> {
+    let (negated, text) = match text.strip_prefix('~') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let nibbles: Vec<char> = text.chars().collect();
+    let [high, low] = nibbles[..] else {
+        return None;
+    };
+    let nibble = |c: char| match c {
+        '?' => Some((0, 0)),
+        _ => c.to_digit(16).map(|it| (it as u8, 0xf)),
+    };
+    let (high, high_mask) = nibble(high)?;
+    let (low, low_mask) = nibble(low)?;
+    Some(HexToken::Byte { value: high << 4 | low, mask: high_mask << 4 | low_mask, negated })
+}
+
+/// Decode the string literal including the quotes
+pub(crate) fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.strip_prefix('"').and_then(|it| it.strip_suffix('"')).unwrap_or(text);
+    let mut res = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            res.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => res.push(b'\n'),
+            Some('t') => res.push(b'\t'),
+            Some('r') => res.push(b'\r'),
+            Some('"') => res.push(b'"'),
+            Some('\\') => res.push(b'\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 => res.push(byte),
+                    _ => return Err(format!("invalid escape sequence `\\x{}`", digits)),
+                }
+            }
+            Some(c) => return Err(format!("unknown escape sequence `\\{}`", c)),
+            None => return Err("unterminated escape sequence".to_string()),
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hir::{BinaryOp, LogicOp},
+        SourceFile,
+    };
+
+    fn lower_text(text: &str) -> (crate::hir::SourceFile, Vec<SyntaxError>) {
+        lower(&SourceFile::parse(text).tree())
+    }
+
+    #[test]
+    fn lower_file() {
+        let text = r#"
+import "pe"
+include "other.yar"
+
+global private rule test : tag1 tag2 {
+    meta:
+        author = "me\x21"
+        version = 0x10
+        score = 1.5
+        active = true
+    strings:
+        $a = "foo\n" wide xor(1-3)
+        $b = { 4D ?A [2-] ( ~00 | 5? ) }
+        $c = /ab+c/is base64
+    condition:
+        pe.number_of_sections == 2 and for any of ($a, $b*) : ( # > 1KB )
+}
+"#;
+        let (file, errors) = lower_text(text);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(file.imports[0].module, "pe");
+        assert_eq!(&text[file.imports[0].span], "import \"pe\"");
+        assert_eq!(file.includes[0].path, "other.yar");
+
+        let rule = &file.rules[0];
+        assert_eq!(rule.name, "test");
+        assert_eq!(rule.modifiers, [RuleModifier::Global, RuleModifier::Private]);
+        assert_eq!(rule.tags, ["tag1", "tag2"]);
+        let meta: Vec<_> = rule.meta.iter().map(|it| (it.key.as_str(), &it.value)).collect();
+        assert_eq!(
+            meta,
+            [
+                ("author", &MetaValue::String(b"me!".to_vec())),
+                ("version", &MetaValue::Int(16)),
+                ("score", &MetaValue::Float(1.5)),
+                ("active", &MetaValue::Bool(true)),
+            ]
+        );
+
+        let [a, b, c] = &rule.patterns[..] else { panic!("{:?}", rule.patterns) };
+        assert_eq!(a.name, "a");
+        assert_eq!(a.kind, PatternKind::Text(b"foo\n".to_vec()));
+        assert_eq!(a.modifiers, [PatternModifier::Wide, PatternModifier::Xor { from: 1, to: 3 }]);
+        assert_eq!(
+            b.kind,
+            PatternKind::Hex(vec![
+                HexToken::Byte { value: 0x4d, mask: 0xff, negated: false },
+                HexToken::Byte { value: 0x0a, mask: 0x0f, negated: false },
+                HexToken::Jump { from: Some(2), to: None },
+                HexToken::Alternative(vec![
+                    vec![HexToken::Byte { value: 0x00, mask: 0xff, negated: true }],
+                    vec![HexToken::Byte { value: 0x50, mask: 0xf0, negated: false }],
+                ]),
+            ])
+        );
+        assert_eq!(
+            c.kind,
+            PatternKind::Regex(Regex {
+                pattern: "ab+c".to_string(),
+                case_insensitive: true,
+                dot_matches_all: true
+            })
+        );
+        assert_eq!(c.modifiers, [PatternModifier::Base64(None)]);
+
+        let condition = rule.condition.as_ref().unwrap();
+        let ExprKind::Binary { op: BinaryOp::LogicOp(LogicOp::And), lhs, rhs } = &condition.kind
+        else {
+            panic!("{:?}", condition)
+        };
+        assert_eq!(&text[lhs.span], "pe.number_of_sections == 2");
+        let ExprKind::For { quantifier: Quantifier::Any, iteration, body } = &rhs.kind else {
+            panic!("{:?}", rhs)
+        };
+        let ForIteration::Patterns(OfSet::Patterns(set)) = iteration else {
+            panic!("{:?}", iteration)
+        };
+        assert_eq!(
+            set.iter().map(|it| (it.name.as_str(), it.wildcard)).collect::<Vec<_>>(),
+            [("a", false), ("b", true)]
+        );
+        let ExprKind::Binary { lhs, rhs, .. } = &body.kind else { panic!("{:?}", body) };
+        assert_eq!(lhs.kind, ExprKind::Count { pattern: String::new(), range: None });
+        assert_eq!(rhs.kind, ExprKind::Int(1024));
+    }
+
+    #[test]
+    fn lowering_errors() {
+        let text = r#"
+rule a {
+    meta:
+        big = 99999999999999999999
+    strings:
+        $a = "\q"
+        $b = "b" xor(3-1)
+        $c = "c"
+    condition:
+        $c
+}
+rule b {
+    condition:
+        1 +
+}
+"#;
+        let (file, errors) = lower_text(text);
+        let errors: Vec<_> = errors.iter().map(|it| (it.message(), &text[it.range()])).collect();
+        assert_eq!(
+            errors,
+            [
+                ("invalid integer `99999999999999999999`", "99999999999999999999"),
+                ("unknown escape sequence `\\q`", "\"\\q\""),
+                ("lower bound of xor range exceeds upper bound", "(3-1)"),
+                ("incomplete expression", "1 +"),
+            ]
+        );
+        assert!(file.rules[0].meta.is_empty());
+        assert_eq!(file.rules[0].patterns.len(), 1);
+        assert!(file.rules[0].condition.is_some());
+        assert!(file.rules[1].condition.is_none());
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(parse_int("10"), Some(10));
+        assert_eq!(parse_int("0x1f"), Some(31));
+        assert_eq!(parse_int("0o17"), Some(15));
+        assert_eq!(parse_int("2MB"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_int("99999999999999999999"), None);
+
+        assert_eq!(unescape(r#""a\tb\x00\"""#), Ok(b"a\tb\x00\"".to_vec()));
+        assert!(unescape(r#""\x0""#).is_err());
+        assert_eq!(parse_hex_byte("zz"), None);
+    }
+
+    #[test]
+    fn hir_is_send() {
+        fn assert_send<T: Send + Sync + 'static>() {}
+        assert_send::<crate::hir::SourceFile>();
+    }
+}
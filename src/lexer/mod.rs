@@ -53,6 +53,12 @@ pub(crate) enum LogosToken {
     Or,
     #[token("not")]
     Not,
+    // C-style boolean operators, they are not valid YARA, but they are lexed
+    // as keywords so the parser can report them and build the intended tree
+    #[token("&&")]
+    DoubleAmpersand,
+    #[token("||")]
+    DoublePipe,
     #[regex(r"true|false", |lex| lex.slice().to_string())]
     Bool(String),
     #[token("contains")]
@@ -280,6 +286,11 @@ pub fn tokenize(text: &str) -> (Vec<Token>, Vec<SyntaxError>) {
                         tokens.push(Token { kind, len: TextSize::from(len as u32) });
                     }
                     continue;
+                // Lone `!` in front of an operand is a C-style negation, not a pattern length
+                } else if matches!(&token, LogosToken::VariableLength(name) if name == "!")
+                    && is_negated_operand(&text[range.end..])
+                {
+                    SyntaxKind::NOT_KW
                 } else {
                     logos_tokenkind_to_syntaxkind(token)
                 }
@@ -299,6 +310,12 @@ pub fn tokenize(text: &str) -> (Vec<Token>, Vec<SyntaxError>) {
     (tokens, errors)
 }
 
+/// Check if the text after a lone `!` starts with something that can be negated
+/// (`!$a`, `!#a > 1`, `!@a[1] == 0`, `!!a` or `!(...)`)
+fn is_negated_operand(rest: &str) -> bool {
+    matches!(rest.trim_start().chars().next(), Some('$' | '#' | '@' | '!' | '('))
+}
+
 // Convert LogosToken to SyntaxKind
 fn logos_tokenkind_to_syntaxkind(token: LogosToken) -> SyntaxKind {
     match token {
@@ -313,6 +330,8 @@ fn logos_tokenkind_to_syntaxkind(token: LogosToken) -> SyntaxKind {
         LogosToken::And => SyntaxKind::AND_KW,
         LogosToken::Or => SyntaxKind::OR_KW,
         LogosToken::Not => SyntaxKind::NOT_KW,
+        LogosToken::DoubleAmpersand => SyntaxKind::AND_KW,
+        LogosToken::DoublePipe => SyntaxKind::OR_KW,
         LogosToken::Contains => SyntaxKind::CONTAINS_KW,
         LogosToken::IContains => SyntaxKind::ICONTAINS_KW,
         LogosToken::StartsWith => SyntaxKind::STARTSWITH_KW,
@@ -607,4 +626,37 @@ mod tests {
         assert_eq!(tokens[23].kind, SyntaxKind::WHITESPACE);
         assert_eq!(tokens[24].kind, SyntaxKind::EOF);
     }
+
+    #[test]
+    fn tokenize_c_style_operators() {
+        let kinds = |input| {
+            let (tokens, errors) = tokenize(input);
+            assert!(errors.is_empty());
+            tokens
+                .iter()
+                .map(|it| it.kind)
+                .filter(|it| !it.is_trivia() && *it != SyntaxKind::EOF)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kinds("$a && $b || !$c"),
+            [
+                SyntaxKind::VARIABLE,
+                SyntaxKind::AND_KW,
+                SyntaxKind::VARIABLE,
+                SyntaxKind::OR_KW,
+                SyntaxKind::NOT_KW,
+                SyntaxKind::VARIABLE
+            ]
+        );
+        assert_eq!(kinds("! (#a > 1)")[..2], [SyntaxKind::NOT_KW, SyntaxKind::L_PAREN]);
+        // Anonymous pattern length and bitwise operators are unchanged
+        assert_eq!(kinds("! > 1")[0], SyntaxKind::VARIABLE_LENGTH);
+        assert_eq!(kinds("!a[1]")[0], SyntaxKind::VARIABLE_LENGTH);
+        assert_eq!(
+            kinds("1 & 2 | 3")[1..4],
+            [SyntaxKind::AMPERSAND, SyntaxKind::INT_LIT, SyntaxKind::PIPE]
+        );
+    }
 }
@@ -4,28 +4,39 @@
 //! It is inspired by the Swift's libSyntax and the Rust's rowan.
 //!
 //! author: Tomáš Ďuriš
-use crate::syntax::{
-    syntax_error::SyntaxError, text_token_source::TextTokenSource, text_tree_sink::TextTreeSink,
-};
-
 pub use crate::parser::SyntaxKind;
+pub use crate::syntax::algo;
 pub use crate::syntax::ast::*;
+pub use crate::syntax::events::{
+    parse_events, parse_with, validate, EventSink, ParseEvent, ParseEvents,
+};
+#[cfg(feature = "serde")]
+pub use crate::syntax::json::JSON_VERSION;
+pub use crate::syntax::line_index::{LineCol, LineIndex, WideEncoding, WideLineCol};
+pub use crate::syntax::printer::{to_dot, to_outline, to_sexpr, PrintOptions};
+pub use crate::syntax::ptr::{AstPtr, SyntaxNodePtr};
+pub use crate::syntax::structural::{StructuralEq, StructuralOptions};
+pub use crate::syntax::syntax_error::{Severity, Suggestion, SyntaxError};
 pub use crate::syntax::syntax_node::{SyntaxNode, SyntaxToken, YARALanguage};
-pub use crate::syntax::Parse;
-pub use crate::syntax::SourceFile;
-pub use rowan_test::{NodeOrToken, WalkEvent};
+pub use crate::syntax::{GreenNode, Parse, SourceFile};
+pub use crate::text_parser::TextError;
+pub use rowan_test::{Direction, NodeOrToken, WalkEvent};
+pub use text_size::{TextRange, TextSize};
 
 // use only for tests
 #[cfg(test)]
 use std::fs;
 #[cfg(test)]
 use std::io::Write;
-#[cfg(test)]
-use text_size::TextRange;
 
+pub mod hir;
 mod lexer;
 mod parser;
+pub mod query;
+pub mod resolve;
+pub mod schema;
 mod syntax;
+mod text_parser;
 
 /// Just a showcase test to see how API for typed layer
 /// of AST could work
@@ -9,6 +9,7 @@ use crate::parser::{
     syntax_kind::T,
     token_set::TokenSet,
     SyntaxKind::{self, *},
+    TokenSuggestion,
 };
 
 /// Parse a source file
@@ -12,6 +12,13 @@ const PATTERN_MODIFIERS_SET: TokenSet = TokenSet::new(&[
     T![base64wide],
 ]);
 
+// Text of the pattern modifiers in `PATTERN_MODIFIERS_SET`, used for suggestions
+const PATTERN_MODIFIERS: [&str; 8] =
+    ["ascii", "wide", "private", "fullword", "nocase", "xor", "base64", "base64wide"];
+
+// Keywords that start a section of the rule body, used for suggestions
+const SECTION_KEYWORDS: [&str; 3] = ["meta", "strings", "condition"];
+
 /// Parse a rule body
 /// A rule body consists `{`, rule_body and `}`
 /// This can probably be later simplified to not have both
@@ -70,7 +77,10 @@ pub(super) fn rule_body(p: &mut Parser) {
                 if has_condition {
                     p.err_and_bump("invalid yara expression");
                 } else {
-                    p.err_and_bump("expected meta, strings or condition keyword");
+                    p.err_and_bump_suggest(
+                        "expected meta, strings or condition keyword",
+                        &SECTION_KEYWORDS,
+                    );
                 }
             }
         }
@@ -164,7 +174,12 @@ pub(super) fn strings_body(p: &mut Parser) {
             m.complete(p, VARIABLE_STMT);
         } else {
             m.abandon(p);
-            p.err_and_bump("expected a new pattern statement or pattern modifier");
+            let candidates: Vec<_> =
+                PATTERN_MODIFIERS.iter().chain(&["condition"]).copied().collect();
+            p.err_and_bump_suggest(
+                "expected a new pattern statement or pattern modifier",
+                &candidates,
+            );
         }
     }
 }
@@ -355,6 +370,8 @@ fn expr_op(p: &mut Parser) -> (u8, SyntaxKind, Associativity) {
 fn expr_stmt_op(p: &mut Parser) -> (u8, SyntaxKind, Associativity) {
     match p.current() {
         T![==] => (6, T![==], Associativity::Left),
+        // `=` is not valid in a condition, it is reported and parsed as `==`
+        T![=] => (6, T![==], Associativity::Left),
         T![!=] => (6, T![!=], Associativity::Left),
         T![contains] => (6, T![contains], Associativity::Left),
         T![icontains] => (6, T![icontains], Associativity::Left),
@@ -372,6 +389,40 @@ fn expr_stmt_op(p: &mut Parser) -> (u8, SyntaxKind, Associativity) {
     }
 }
 
+/// Consume the operator `op`
+/// C-style operators (`&&`, `||`, `!` and `=`) are reported together with a fix
+/// and added to the tree as the YARA operator the user most likely meant
+fn bump_operator(p: &mut Parser, op: SyntaxKind) {
+    let yara_op = match (p.current(), p.current_text()) {
+        (T![and], "&&") => "and",
+        (T![or], "||") => "or",
+        (T![not], "!") => "not",
+        (T![=], _) => "==",
+        _ => {
+            p.bump(op);
+            return;
+        }
+    };
+
+    let c_op = p.current_text().to_string();
+    // `$a&&$b` and `!$a` need spaces around the keyword
+    let mut replacement = yara_op.to_string();
+    if p.is_jointed_to_prev() {
+        replacement.insert(0, ' ');
+    }
+    if p.is_jointed_to_next() {
+        replacement.push(' ');
+    }
+    p.error_with_suggestions(
+        format!("use `{}` instead of `{}`", yara_op, c_op),
+        vec![TokenSuggestion {
+            label: format!("replace `{}` with `{}`", c_op, yara_op),
+            replacement,
+        }],
+    );
+    p.bump_remap(op);
+}
+
 /// Parse an expression using a Pratt parser.
 ///
 /// Expression can be binary, unary or literal
@@ -396,7 +447,7 @@ fn boolean_expr(p: &mut Parser, m: Option<Marker>, bp: u8) -> Option<CompletedMa
             break;
         }
         let m = lhs.precede(p);
-        p.bump(op);
+        bump_operator(p, op);
 
         let op_bp = match associativity {
             Associativity::Left => op_bp + 1,
@@ -416,7 +467,7 @@ fn boolean_term(p: &mut Parser) -> Option<CompletedMarker> {
     let m = p.start();
     match p.current() {
         T![not] => {
-            p.bump(T![not]);
+            bump_operator(p, T![not]);
             boolean_term(p);
         }
         T![variable] => {
@@ -499,7 +550,7 @@ fn expr_stmt(p: &mut Parser, m: Option<Marker>, bp: u8) -> Option<CompletedMarke
             break;
         }
         let m = lhs.precede(p);
-        p.bump(op);
+        bump_operator(p, op);
 
         let op_bp = match associativity {
             Associativity::Left => op_bp + 1,
@@ -11,12 +11,24 @@ mod event;
 mod grammar;
 #[allow(clippy::module_inception)]
 mod parser;
+pub(crate) mod suggest;
 mod token_set;
 
 use grammar::parse_source_file;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct ParseError(pub String);
+pub struct ParseError {
+    pub message: String,
+    /// Possible replacements of the token at which the error was reported
+    pub suggestions: Vec<TokenSuggestion>,
+}
+
+/// Suggestion to replace the current token with another text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TokenSuggestion {
+    pub label: String,
+    pub replacement: String,
+}
 
 /// `TokenSource` abstracts the source of the tokens parser uses.
 ///
@@ -33,6 +45,9 @@ pub trait TokenSource {
 
     /// Check if the current token is keyword
     fn is_keyword(&self, kw: &str) -> bool;
+
+    /// Text of the current token
+    fn current_text(&self) -> &str;
 }
 
 /// `Token` abstracts the cursor for `TokenSource`
@@ -40,6 +55,7 @@ pub trait TokenSource {
 pub struct Token {
     pub kind: SyntaxKind,
 
+    pub is_jointed_to_prev: bool,
     pub is_jointed_to_next: bool,
 }
 
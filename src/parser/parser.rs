@@ -4,10 +4,11 @@ use drop_bomb::DropBomb;
 
 use crate::parser::{
     event::Event,
+    suggest::did_you_mean,
     token_set::TokenSet,
     ParseError,
     SyntaxKind::{self, EOF, ERROR, TOMBSTONE},
-    TokenSource,
+    TokenSource, TokenSuggestion,
 };
 
 /// This structure provides API for navigating through the token stream
@@ -43,6 +44,11 @@ impl<'t> Parser<'t> {
         self.token_source.lookahead_nth(n).kind
     }
 
+    /// Returns the text of the current token
+    pub(crate) fn current_text(&self) -> &str {
+        self.token_source.current_text()
+    }
+
     /// Check if the current token is specific `SyntaxKind` kind
     pub(crate) fn at(&self, kind: SyntaxKind) -> bool {
         // currently we don't need support for composite tokens (e.g. `>>`)
@@ -80,6 +86,25 @@ impl<'t> Parser<'t> {
         assert!(self.eat(kind));
     }
 
+    /// Consume the next token, but add it to the tree as a token of another kind
+    /// This is used to recover from common mistakes, like `=` instead of `==`
+    pub(crate) fn bump_remap(&mut self, kind: SyntaxKind) {
+        if self.nth(0) == EOF {
+            return;
+        }
+        self.do_bump(kind, 1);
+    }
+
+    /// Check if the current token is directly followed by the next one, without any trivia
+    pub(crate) fn is_jointed_to_next(&self) -> bool {
+        self.token_source.lookahead_nth(0).is_jointed_to_next
+    }
+
+    /// Check if there is no trivia between the previous token and the current one
+    pub(crate) fn is_jointed_to_prev(&self) -> bool {
+        self.token_source.lookahead_nth(0).is_jointed_to_prev
+    }
+
     /// Consume any token
     pub(crate) fn bump_any(&mut self) {
         let kind = self.nth(0);
@@ -107,7 +132,16 @@ impl<'t> Parser<'t> {
     /// Right now `ParseError` is just converted to `SyntaxError`
     /// after the parsing is done and uses just token offset (not range)
     pub(crate) fn error<T: Into<String>>(&mut self, message: T) {
-        let msg = ParseError(message.into());
+        self.error_with_suggestions(message, Vec::new());
+    }
+
+    /// Report an error together with suggested replacements of the current token
+    pub(crate) fn error_with_suggestions<T: Into<String>>(
+        &mut self,
+        message: T,
+        suggestions: Vec<TokenSuggestion>,
+    ) {
+        let msg = ParseError { message: message.into(), suggestions };
         self.push_event(Event::Error { msg });
     }
 
@@ -126,6 +160,20 @@ impl<'t> Parser<'t> {
         self.err_recover(message, TokenSet::EMPTY)
     }
 
+    /// Create an error node and consume the next token, the error suggests
+    /// replacing the token with one of the similar `candidates`
+    pub(crate) fn err_and_bump_suggest(&mut self, message: &str, candidates: &[&str]) {
+        let suggestions = if self.at(SyntaxKind::IDENTIFIER) {
+            did_you_mean(self.current_text(), candidates.iter().copied())
+        } else {
+            Vec::new()
+        };
+        let m = self.start();
+        self.error_with_suggestions(message, suggestions);
+        self.bump_any();
+        m.complete(self, ERROR);
+    }
+
     /// Create an error node and consume the next token if it is of expected kind
     /// If the current token belongs to given recovery set, it just reports and error
     /// and tries to recover
@@ -0,0 +1,88 @@
+//! "Did you mean" suggestions for misspelled words
+//!
+//! Candidates are ranked by the Levenshtein distance from the misspelled word.
+//! Only candidates that are close enough are returned, the limit grows with
+//! the length of the word (one edit per three characters, at least one edit).
+//! A candidate that would replace every character of the word is never suggested.
+
+use crate::parser::TokenSuggestion;
+
+/// Suggest replacing `word` with one of the similar `candidates`
+pub(crate) fn did_you_mean<'a>(
+    word: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<TokenSuggestion> {
+    similar_words(word, candidates)
+        .into_iter()
+        .map(|it| TokenSuggestion {
+            label: format!("did you mean `{}`?", it),
+            replacement: it.to_string(),
+        })
+        .collect()
+}
+
+/// Returns the candidates that are similar to `word`, the closest ones first
+pub(crate) fn similar_words<'a>(
+    word: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let len = word.chars().count();
+    let max_distance = len.max(3) / 3;
+    let mut res: Vec<_> = candidates
+        .into_iter()
+        .filter(|it| *it != word)
+        .map(|it| (edit_distance(word, it), it))
+        .filter(|(distance, _)| *distance <= max_distance && *distance < len)
+        .collect();
+    res.sort_by_key(|(distance, _)| *distance);
+    res.dedup_by_key(|(_, it)| *it);
+    res.into_iter().map(|(_, it)| it).collect()
+}
+
+/// Levenshtein distance between two strings, computed on characters
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("wide", ""), 4);
+        assert_eq!(edit_distance("condtion", "condition"), 1);
+        assert_eq!(edit_distance("stirngs", "strings"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn similar() {
+        let sections = ["meta", "strings", "condition"];
+        assert_eq!(similar_words("condtion", sections), ["condition"]);
+        assert_eq!(similar_words("stirngs", sections), ["strings"]);
+        assert_eq!(similar_words("string", sections), ["strings"]);
+        assert!(similar_words("a", sections).is_empty());
+        assert!(similar_words("a", ["b"]).is_empty());
+        assert_eq!(similar_words("ab", ["b", "cd"]), ["b"]);
+        assert!(similar_words("strings", sections).is_empty());
+
+        let modifiers = ["ascii", "wide", "nocase", "base64", "base64wide"];
+        assert_eq!(similar_words("widee", modifiers), ["wide"]);
+        assert_eq!(similar_words("nocas", modifiers), ["nocase"]);
+        assert_eq!(similar_words("base64wid", modifiers), ["base64wide", "base64"]);
+    }
+}
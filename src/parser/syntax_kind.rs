@@ -6,6 +6,8 @@
 
 mod generated;
 
+use std::{collections::HashMap, sync::OnceLock};
+
 #[allow(unreachable_pub)]
 pub use self::generated::{SyntaxKind, T};
 
@@ -29,4 +31,16 @@ impl SyntaxKind {
     pub fn is_trivia(self) -> bool {
         matches!(self, SyntaxKind::WHITESPACE | SyntaxKind::COMMENT)
     }
+
+    /// Kind with the name as printed by `Debug`, for example `RULE` or `VARIABLE_STMT`
+    pub fn from_name(name: &str) -> Option<SyntaxKind> {
+        static NAMES: OnceLock<HashMap<String, SyntaxKind>> = OnceLock::new();
+        let names = NAMES.get_or_init(|| {
+            (0..SyntaxKind::__LAST as u16)
+                .map(SyntaxKind::from)
+                .map(|it| (format!("{:?}", it), it))
+                .collect()
+        });
+        names.get(name).copied()
+    }
 }
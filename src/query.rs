@@ -0,0 +1,570 @@
+//! Structural queries over the syntax tree
+//!
+//! Queries are written as S-expressions in the spirit of tree-sitter queries.
+//! A query consists of one or more patterns, every pattern is matched
+//! against each node and token of the tree:
+//!
+//! - `(KIND child...)` matches a node or token of the `SyntaxKind` with that name,
+//!   `(_ child...)` matches any node
+//! - `_` matches any node or token except trivia
+//! - `"text"` matches a token with exactly that text
+//! - child patterns match descendants of the node, not only its direct children,
+//!   so wrapper nodes like `PRIMARY_EXPR` do not have to be spelled out.
+//!   They match in the order they are written and cannot overlap, so in
+//!   `(RULE (VARIABLE) @a (VARIABLE) @b)` the element `@b` always comes after `@a`
+//! - `name: pattern`, `lhs: pattern`, `rhs: pattern` and `op: pattern` match
+//!   the element returned by the typed accessor of the same name instead
+//! - `@name` after a pattern captures the matched element
+//! - `(#predicate? args...)` inside a pattern filters matches by their captures
+//! - `;` starts a comment until the end of the line
+//!
+//! Supported predicates are `#eq?` and `#not-eq?` with a string or another capture,
+//! `#contains?`, `#starts-with?` and `#ends-with?` with a string,
+//! `#lt?`, `#le?`, `#gt?` and `#ge?` comparing the capture as an integer
+//! and `#count-lt?`, `#count-eq?` and `#count-gt?` counting descendants of a given kind.
+//! If a capture name is used more than once, the predicate must hold for all its captures.
+//!
+//! For example, rules calling `pe.imphash()` with fewer than 3 patterns:
+//!
+//! ```text
+//! (RULE
+//!   (FUNCTION_CALL_EXPR (PRIMARY_EXPR) @callee)
+//!   (#eq? @callee "pe.imphash")
+//!   (#count-lt? @rule VARIABLE_STMT 3)) @rule
+//! ```
+
+mod parse;
+
+use std::{cmp::Ordering, collections::HashSet};
+
+use text_size::{TextRange, TextSize};
+
+use crate::{
+    hir,
+    syntax::{
+        ast::{self, AstNode, AstToken, HasName},
+        syntax_node::SyntaxElement,
+    },
+    text_parser::TextError,
+    NodeOrToken, SyntaxKind, SyntaxNode, SyntaxToken,
+};
+
+/// Compiled query, it can be used to match any number of trees
+#[derive(Debug, Clone)]
+pub struct Query {
+    patterns: Vec<TopPattern>,
+}
+
+impl Query {
+    /// Parse and validate the query
+    pub fn compile(src: &str) -> Result<Query, QueryError> {
+        Ok(Query { patterns: parse::parse(src)? })
+    }
+
+    /// Number of top-level patterns in the query
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Names of all captures in the query, without the `@`, in order of appearance
+    pub fn capture_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for pattern in &self.patterns {
+            pattern.pattern.capture_names(&mut names);
+        }
+        names
+    }
+
+    /// Find all matches of the query in the tree
+    ///
+    /// Matches are ordered by the position of the matched element in the tree
+    /// and then by the index of the pattern. One element can be matched more than once
+    /// if the child patterns can be matched by different descendants.
+    pub fn matches(&self, root: &impl AstNode) -> Vec<QueryMatch> {
+        let mut res = Vec::new();
+        let mut found = HashSet::new();
+        for element in root.syntax().descendants_with_tokens() {
+            found.clear();
+            for (pattern_index, pattern) in self.patterns.iter().enumerate() {
+                for captures in pattern.pattern.matches(&element) {
+                    let matched = QueryMatch { pattern_index, captures };
+                    if pattern.predicates.iter().all(|it| it.holds(&matched.captures))
+                        && found.insert(matched.clone())
+                    {
+                        res.push(matched);
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Error in the query text
+pub type QueryError = TextError;
+
+/// Single match of a top-level pattern
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryMatch {
+    /// Index of the matched pattern in the query
+    pub pattern_index: usize,
+    pub captures: Vec<QueryCapture>,
+}
+
+impl QueryMatch {
+    /// First capture with the name, without the `@`
+    pub fn capture(&self, name: &str) -> Option<&QueryCapture> {
+        self.captures.iter().find(|it| it.name == name)
+    }
+}
+
+/// Element captured by `@name`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryCapture {
+    /// Name of the capture without the `@`
+    pub name: String,
+    pub element: NodeOrToken<SyntaxNode, SyntaxToken>,
+}
+
+impl QueryCapture {
+    pub fn range(&self) -> TextRange {
+        self.element.text_range()
+    }
+
+    pub fn text(&self) -> String {
+        match &self.element {
+            NodeOrToken::Node(node) => node.to_string(),
+            NodeOrToken::Token(token) => token.text().to_string(),
+        }
+    }
+}
+
+/// Pattern with the predicates written anywhere inside of it
+#[derive(Debug, Clone)]
+struct TopPattern {
+    pattern: Pattern,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    matcher: Matcher,
+    children: Vec<Child>,
+    captures: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Kind(SyntaxKind),
+    /// `(_)`
+    AnyNode,
+    /// `_`
+    Any,
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+struct Child {
+    field: Option<Field>,
+    pattern: Pattern,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Lhs,
+    Rhs,
+    Op,
+}
+
+#[derive(Debug, Clone)]
+struct Predicate {
+    kind: PredicateKind,
+    args: Vec<Arg>,
+    range: TextRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PredicateKind {
+    Eq,
+    NotEq,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    CountLt,
+    CountEq,
+    CountGt,
+}
+
+#[derive(Debug, Clone)]
+enum Arg {
+    Capture(String),
+    Str(String),
+    Int(i64),
+    Kind(SyntaxKind),
+}
+
+impl Pattern {
+    fn capture_names(&self, names: &mut Vec<String>) {
+        for name in &self.captures {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        for child in &self.children {
+            child.pattern.capture_names(names);
+        }
+    }
+
+    /// All possible sets of captures if the pattern matches the element,
+    /// an empty list if it does not match
+    fn matches(&self, element: &SyntaxElement) -> Vec<Vec<QueryCapture>> {
+        let matched = match &self.matcher {
+            Matcher::Kind(kind) => element.kind() == *kind,
+            Matcher::AnyNode => element.as_node().is_some(),
+            Matcher::Any => !element.kind().is_trivia(),
+            Matcher::Text(text) => element.as_token().is_some_and(|it| it.text() == text),
+        };
+        if !matched {
+            return Vec::new();
+        }
+        // Matches of every child pattern are found once, together with the range of
+        // the matched element, and only then combined in the order of the children
+        let mut alternatives = Vec::with_capacity(self.children.len());
+        for child in &self.children {
+            let candidates: Vec<SyntaxElement> = match (child.field, element) {
+                (Some(field), _) => field.resolve(element).into_iter().collect(),
+                (None, NodeOrToken::Node(node)) => node.descendants_with_tokens().skip(1).collect(),
+                (None, NodeOrToken::Token(_)) => Vec::new(),
+            };
+            let matches: Vec<_> = candidates
+                .iter()
+                .flat_map(|it| child.pattern.matches(it).into_iter().map(|m| (it.text_range(), m)))
+                .collect();
+            if matches.is_empty() {
+                return Vec::new();
+            }
+            alternatives.push(matches);
+        }
+        let mut captures: Vec<_> = self
+            .captures
+            .iter()
+            .map(|name| QueryCapture { name: name.clone(), element: element.clone() })
+            .collect();
+        let mut res = Vec::new();
+        self.combine(&alternatives, 0.into(), &mut captures, &mut res);
+        res
+    }
+
+    /// Add all combinations of the matches of the children to `res`,
+    /// children without a field match elements that start after `after`
+    fn combine(
+        &self,
+        alternatives: &[Vec<(TextRange, Vec<QueryCapture>)>],
+        after: TextSize,
+        captures: &mut Vec<QueryCapture>,
+        res: &mut Vec<Vec<QueryCapture>>,
+    ) {
+        let index = self.children.len() - alternatives.len();
+        let Some((matches, rest)) = alternatives.split_first() else {
+            res.push(captures.clone());
+            return;
+        };
+        let positional = self.children[index].field.is_none();
+        for (range, matched) in matches {
+            if positional && range.start() < after {
+                continue;
+            }
+            let len = captures.len();
+            captures.extend(matched.iter().cloned());
+            self.combine(rest, if positional { range.end() } else { after }, captures, res);
+            captures.truncate(len);
+        }
+    }
+}
+
+impl Field {
+    fn resolve(self, element: &SyntaxElement) -> Option<SyntaxElement> {
+        fn node<N: AstNode>(node: Option<N>) -> Option<SyntaxElement> {
+            node.map(|it| it.syntax().clone().into())
+        }
+        fn token(token: Option<SyntaxToken>) -> Option<SyntaxElement> {
+            token.map(Into::into)
+        }
+
+        let element = element.as_node()?.clone();
+        if self == Field::Name {
+            return ast::AnyHasName::cast(element)?.name().map(|it| it.syntax().clone().into());
+        }
+        let (lhs, rhs, op) = match element.kind() {
+            SyntaxKind::EXPR_BODY => {
+                let it = ast::ExprBody::cast(element)?;
+                (node(it.lhs()), node(it.rhs()), it.op_token())
+            }
+            SyntaxKind::BOOLEAN_EXPR => {
+                let it = ast::BooleanExpr::cast(element)?;
+                (node(it.lhs()), node(it.rhs()), it.op_token())
+            }
+            SyntaxKind::BOOLEAN_TERM_EXPR => {
+                let it = ast::BooleanTermExpr::cast(element)?;
+                (node(it.lhs()), node(it.rhs()), it.op_token())
+            }
+            SyntaxKind::RANGE => {
+                let it = ast::Range::cast(element)?;
+                (node(it.lhs()), node(it.rhs()), None)
+            }
+            SyntaxKind::XOR_RANGE => {
+                let it = ast::XorRange::cast(element)?;
+                (token(it.lhs()), token(it.rhs()), None)
+            }
+            SyntaxKind::HEX_JUMP => {
+                let it = ast::HexJump::cast(element)?;
+                (token(it.lhs()), token(it.rhs()), None)
+            }
+            _ => return None,
+        };
+        match self {
+            Field::Lhs => lhs,
+            Field::Rhs => rhs,
+            Field::Op => token(op),
+            Field::Name => unreachable!(),
+        }
+    }
+}
+
+impl Predicate {
+    fn holds(&self, captures: &[QueryCapture]) -> bool {
+        fn with_name<'a>(
+            captures: &'a [QueryCapture],
+            name: &'a str,
+        ) -> impl Iterator<Item = &'a QueryCapture> {
+            captures.iter().filter(move |it| it.name == name)
+        }
+        let (subject, args) = match &self.args[..] {
+            [Arg::Capture(name), args @ ..] => (name, args),
+            _ => unreachable!("arguments are checked when the query is compiled"),
+        };
+        let mut subjects = with_name(captures, subject);
+        match (self.kind, args) {
+            (PredicateKind::Eq, [Arg::Str(text)]) => subjects.all(|it| it.text() == *text),
+            (PredicateKind::NotEq, [Arg::Str(text)]) => subjects.all(|it| it.text() != *text),
+            (kind @ (PredicateKind::Eq | PredicateKind::NotEq), [Arg::Capture(other)]) => subjects
+                .all(|it| {
+                    with_name(captures, other)
+                        .all(|other| (it.text() == other.text()) == (kind == PredicateKind::Eq))
+                }),
+            (PredicateKind::Contains, [Arg::Str(text)]) => {
+                subjects.all(|it| it.text().contains(text.as_str()))
+            }
+            (PredicateKind::StartsWith, [Arg::Str(text)]) => {
+                subjects.all(|it| it.text().starts_with(text.as_str()))
+            }
+            (PredicateKind::EndsWith, [Arg::Str(text)]) => {
+                subjects.all(|it| it.text().ends_with(text.as_str()))
+            }
+            (kind, [Arg::Int(value)]) => subjects.all(|it| {
+                hir::parse_int(&it.text()).is_some_and(|it| compare(kind, it.cmp(value)))
+            }),
+            (kind, [Arg::Kind(counted), Arg::Int(value)]) => subjects.all(|it| {
+                let count = match &it.element {
+                    NodeOrToken::Node(node) => {
+                        node.descendants_with_tokens().filter(|it| it.kind() == *counted).count()
+                    }
+                    NodeOrToken::Token(_) => 0,
+                };
+                compare(kind, (count as i64).cmp(value))
+            }),
+            _ => unreachable!("arguments are checked when the query is compiled"),
+        }
+    }
+}
+
+fn compare(kind: PredicateKind, ordering: Ordering) -> bool {
+    match kind {
+        PredicateKind::Lt | PredicateKind::CountLt => ordering.is_lt(),
+        PredicateKind::Le => ordering.is_le(),
+        PredicateKind::Gt | PredicateKind::CountGt => ordering.is_gt(),
+        PredicateKind::Ge => ordering.is_ge(),
+        PredicateKind::CountEq => ordering.is_eq(),
+        _ => unreachable!("not a comparison"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceFile;
+
+    const TEXT: &str = r#"
+import "pe"
+
+rule imphash : malware {
+    strings:
+        $a = { 00 [2-150] 01 }
+        $b = "b"
+    condition:
+        pe.imphash() == "abc" and $a
+}
+
+rule many {
+    strings:
+        $a = { 00 [4] 01 }
+        $b = "b"
+        $c = "c"
+    condition:
+        pe.imphash() == "def" and math.entropy(0, filesize) > 7
+}
+
+rule other {
+    condition:
+        uint16(0) == 0x5a4d
+}
+"#;
+
+    fn matches(query: &str) -> Vec<Vec<(String, String)>> {
+        let file = SourceFile::parse(TEXT).tree();
+        Query::compile(query)
+            .unwrap()
+            .matches(&file)
+            .into_iter()
+            .map(|it| it.captures.into_iter().map(|it| (it.name.clone(), it.text())).collect())
+            .collect()
+    }
+
+    fn names(query: &str, capture: &str) -> Vec<String> {
+        let file = SourceFile::parse(TEXT).tree();
+        Query::compile(query)
+            .unwrap()
+            .matches(&file)
+            .iter()
+            .map(|it| it.capture(capture).unwrap().text())
+            .collect()
+    }
+
+    #[test]
+    fn kinds_and_fields() {
+        assert_eq!(names("(RULE name: _ @name)", "name"), ["imphash", "many", "other"]);
+        assert_eq!(names(r#"(RULE (TAG "malware")) @rule"#, "rule").len(), 1);
+        assert_eq!(
+            names(r#"(BOOLEAN_TERM_EXPR op: "==" rhs: (_) @value)"#, "value"),
+            [r#""abc""#, r#""def""#, "0x5a4d"]
+        );
+        assert_eq!(names("(HEX_JUMP lhs: _ @lhs rhs: _)", "lhs"), ["2"]);
+
+        // Captures of nested patterns are combined
+        let res = matches(r#"(VARIABLE_STMT name: _ @name (STRING_LIT) @value) @stmt"#);
+        assert_eq!(res.len(), 3);
+        assert_eq!(
+            res[0],
+            [
+                ("stmt".to_string(), r#"$b = "b""#.to_string()),
+                ("name".to_string(), "$b".to_string()),
+                ("value".to_string(), r#""b""#.to_string()),
+            ]
+        );
+
+        // Multiple patterns, `;` comments
+        let file = SourceFile::parse(TEXT).tree();
+        let query =
+            Query::compile("(IMPORT_STMT) @import ; imports\n(INCLUDE_STMT) @include").unwrap();
+        let res = query.matches(&file);
+        assert_eq!(query.pattern_count(), 2);
+        assert_eq!(query.capture_names(), ["import", "include"]);
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].pattern_index, 0);
+        assert_eq!(res[0].captures[0].range(), TextRange::new(1.into(), 12.into()));
+    }
+
+    #[test]
+    fn predicates() {
+        let imphash = r#"
+            (RULE
+                (CONDITION (FUNCTION_CALL_EXPR (PRIMARY_EXPR) @callee))
+                (#eq? @callee "pe.imphash")
+                (#count-lt? @rule VARIABLE_STMT 3)) @rule"#;
+        let res = matches(imphash);
+        assert_eq!(res.len(), 1);
+        assert!(res[0][0].1.starts_with("rule imphash"));
+
+        let jumps = "(VARIABLE_STMT (HEX_JUMP (INT_LIT) @n) (#gt? @n 100)) @pattern";
+        assert_eq!(names(jumps, "pattern"), ["$a = { 00 [2-150] 01 }"]);
+
+        assert_eq!(
+            names(r#"(FUNCTION_CALL_EXPR (PRIMARY_EXPR) @f (#starts-with? @f "math"))"#, "f"),
+            ["math.entropy"]
+        );
+        assert_eq!(
+            names(r#"(RULE name: _ @n (#not-eq? @n "many") (#ends-with? @n "r"))"#, "n"),
+            ["other"]
+        );
+        assert_eq!(names(r#"(RULE name: _ @n (#contains? @n "ph"))"#, "n"), ["imphash"]);
+        assert_eq!(names("(RULE (#count-eq? @r VARIABLE_STMT 0)) @r", "r").len(), 1);
+        assert_eq!(names("(RULE (#count-gt? @r VARIABLE_STMT 2)) @r", "r").len(), 1);
+        assert_eq!(names("(HEX_JUMP (INT_LIT) @i (#le? @i 3))", "i"), ["2"]);
+        assert_eq!(names("(PRIMARY_EXPR (INT_LIT) @i (#ge? @i 0x5a4d))", "i"), ["0x5a4d"]);
+        assert_eq!(
+            names(
+                "(RULE (STRINGS (VARIABLE) @decl) (CONDITION (VARIABLE) @used) (#eq? @decl @used))",
+                "decl"
+            ),
+            ["$a"]
+        );
+    }
+
+    #[test]
+    fn many_children() {
+        let strings: String = (0..30).map(|i| format!("$s{} = \"{}\" ", i, i)).collect();
+        let text = format!("rule a {{ strings: {} condition: any of them }}", strings);
+        let file = SourceFile::parse(&text).tree();
+
+        // Children match in order without overlapping, so every triple is found once
+        let query = Query::compile("(RULE (VARIABLE) @a (VARIABLE) @b (VARIABLE) @c)").unwrap();
+        let res = query.matches(&file);
+        assert_eq!(res.len(), 30 * 29 * 28 / 6);
+        assert!(res
+            .iter()
+            .all(|it| it.captures[0].range().end() <= it.captures[1].range().start()));
+        assert_eq!(res[0].captures[2].text(), "$s2");
+
+        let query =
+            Query::compile("(RULE (VARIABLE_STMT) (VARIABLE_STMT) (STRINGS)) @rule").unwrap();
+        assert!(query.matches(&file).is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let error = |query: &str| {
+            let error = Query::compile(query).unwrap_err();
+            (error.message().to_string(), error.range())
+        };
+        let range = |start: u32, end: u32| TextRange::new(start.into(), end.into());
+        assert_eq!(error(""), ("empty query".to_string(), range(0, 0)));
+        assert_eq!(error("(RULES)"), ("unknown syntax kind `RULES`".to_string(), range(1, 6)));
+        assert_eq!(error("(RULE"), ("expected `)`".to_string(), range(5, 5)));
+        assert_eq!(error("(RULE foo: _)"), ("unknown field `foo`".to_string(), range(6, 10)));
+        assert_eq!(error("(RULE \"x)"), ("unterminated string".to_string(), range(6, 9)));
+        assert_eq!(error("(RULE \"x\\"), ("unterminated string".to_string(), range(6, 9)));
+        assert_eq!(error("(RULE) @"), ("expected a name after `@`".to_string(), range(7, 8)));
+        assert_eq!(
+            error("(RULE (#eq? @x \"a\")) @r"),
+            ("unknown capture `@x`".to_string(), range(6, 19))
+        );
+        assert_eq!(
+            error("(RULE (#equal? @r \"a\")) @r"),
+            ("unknown predicate `#equal?`".to_string(), range(6, 22))
+        );
+        assert_eq!(
+            error("(RULE (#gt? @r \"a\")) @r"),
+            ("invalid arguments of `#gt?`".to_string(), range(6, 19))
+        );
+        assert_eq!(error("(RULE) $"), ("unexpected character `$`".to_string(), range(7, 8)));
+        assert_eq!(
+            Query::compile("(RULE) )").unwrap_err().to_string(),
+            "expected a pattern at 7..8"
+        );
+    }
+}
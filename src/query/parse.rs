@@ -0,0 +1,245 @@
+//! Parser of the query language
+//!
+//! The query is first split into tokens, then the tokens are parsed
+//! into patterns. Errors point to the offending part of the query text.
+
+use text_size::{TextRange, TextSize};
+
+use crate::{
+    hir,
+    query::{
+        Arg, Child, Field, Matcher, Pattern, Predicate, PredicateKind, QueryError, TopPattern,
+    },
+    text_parser::{self, offset, string_lit},
+    SyntaxKind,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    /// `@name`
+    Capture(String),
+    /// `#name`
+    Predicate(String),
+    /// `name:`
+    Field(String),
+    Str(String),
+    /// Kind name, `_` or number
+    Word(String),
+}
+
+pub(super) fn parse(src: &str) -> Result<Vec<TopPattern>, QueryError> {
+    let tokens = tokenize(src)?;
+    let mut parser = Parser::new(tokens, src);
+    let mut patterns = Vec::new();
+    while !parser.at_end() {
+        let mut predicates = Vec::new();
+        let pattern = parser.pattern(&mut predicates)?;
+        let mut names = Vec::new();
+        pattern.capture_names(&mut names);
+        for predicate in &predicates {
+            for arg in &predicate.args {
+                if let Arg::Capture(name) = arg {
+                    if !names.contains(name) {
+                        let message = format!("unknown capture `@{}`", name);
+                        return Err(QueryError::new(message, predicate.range));
+                    }
+                }
+            }
+        }
+        patterns.push(TopPattern { pattern, predicates });
+    }
+    if patterns.is_empty() {
+        return Err(QueryError::new("empty query", TextRange::empty(0.into())));
+    }
+    Ok(patterns)
+}
+
+fn tokenize(src: &str) -> Result<Vec<(Token, TextRange)>, QueryError> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '?' | '.');
+    let mut res = Vec::new();
+    let mut chars = src.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            ';' => {
+                // Comment until the end of the line
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                continue;
+            }
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '"' => Token::Str(string_lit(&mut chars, start, src)?),
+            c if is_word(c) || c == '@' || c == '#' => {
+                let mut word = String::new();
+                while let Some((_, c)) = chars.next_if(|(_, c)| is_word(*c)) {
+                    word.push(c);
+                }
+                match c {
+                    '@' | '#' if word.is_empty() => {
+                        let range = TextRange::at(offset(start), TextSize::of(c));
+                        return Err(QueryError::new(
+                            format!("expected a name after `{}`", c),
+                            range,
+                        ));
+                    }
+                    '@' => Token::Capture(word),
+                    '#' => Token::Predicate(word),
+                    _ => {
+                        word.insert(0, c);
+                        if chars.next_if(|(_, c)| *c == ':').is_some() {
+                            Token::Field(word)
+                        } else {
+                            Token::Word(word)
+                        }
+                    }
+                }
+            }
+            _ => {
+                let range = TextRange::at(offset(start), TextSize::of(c));
+                return Err(QueryError::new(format!("unexpected character `{}`", c), range));
+            }
+        };
+        let end = chars.peek().map_or(src.len(), |(it, _)| *it);
+        res.push((token, TextRange::new(offset(start), offset(end))));
+    }
+    Ok(res)
+}
+
+type Parser = text_parser::Parser<Token>;
+
+impl Parser {
+    /// Pattern with its captures, predicates inside of it are added to `predicates`
+    fn pattern(&mut self, predicates: &mut Vec<Predicate>) -> Result<Pattern, QueryError> {
+        let (matcher, children) = match self.peek() {
+            Some(Token::Word(word)) if word == "_" => {
+                self.bump();
+                (Matcher::Any, Vec::new())
+            }
+            Some(Token::Str(text)) => {
+                let matcher = Matcher::Text(text.clone());
+                self.bump();
+                (matcher, Vec::new())
+            }
+            Some(Token::LParen) => {
+                self.bump();
+                let matcher = match self.peek() {
+                    Some(Token::Word(word)) if word == "_" => Matcher::AnyNode,
+                    Some(Token::Word(word)) => match SyntaxKind::from_name(word) {
+                        Some(kind) => Matcher::Kind(kind),
+                        None => return self.error(format!("unknown syntax kind `{}`", word)),
+                    },
+                    _ => return self.error("expected a syntax kind or `_`"),
+                };
+                self.bump();
+                let children = self.children(predicates)?;
+                (matcher, children)
+            }
+            _ => return self.error("expected a pattern"),
+        };
+        let mut captures = Vec::new();
+        while let Some(Token::Capture(name)) = self.peek() {
+            captures.push(name.clone());
+            self.bump();
+        }
+        Ok(Pattern { matcher, children, captures })
+    }
+
+    /// Child patterns and predicates until the closing parenthesis
+    fn children(&mut self, predicates: &mut Vec<Predicate>) -> Result<Vec<Child>, QueryError> {
+        let mut children = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::RParen) => {
+                    self.bump();
+                    return Ok(children);
+                }
+                None => return self.error("expected `)`"),
+                Some(Token::LParen) if matches!(self.peek_nth(1), Some(Token::Predicate(_))) => {
+                    predicates.push(self.predicate()?);
+                }
+                Some(Token::Field(name)) => {
+                    let field = match name.as_str() {
+                        "name" => Field::Name,
+                        "lhs" => Field::Lhs,
+                        "rhs" => Field::Rhs,
+                        "op" => Field::Op,
+                        _ => return self.error(format!("unknown field `{}`", name)),
+                    };
+                    self.bump();
+                    let pattern = self.pattern(predicates)?;
+                    children.push(Child { field: Some(field), pattern });
+                }
+                Some(_) => {
+                    let pattern = self.pattern(predicates)?;
+                    children.push(Child { field: None, pattern });
+                }
+            }
+        }
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, QueryError> {
+        let start = self.range().start();
+        self.bump();
+        let name = match self.bump() {
+            Some(Token::Predicate(name)) => name,
+            _ => unreachable!("predicates are checked before they are parsed"),
+        };
+        let mut args = Vec::new();
+        loop {
+            let arg = match self.peek() {
+                Some(Token::RParen) => break,
+                Some(Token::Capture(name)) => Arg::Capture(name.clone()),
+                Some(Token::Str(text)) => Arg::Str(text.clone()),
+                Some(Token::Word(word)) => {
+                    match (hir::parse_int(word), SyntaxKind::from_name(word)) {
+                        (Some(value), _) => Arg::Int(value),
+                        (_, Some(kind)) => Arg::Kind(kind),
+                        _ => return self.error(format!("unknown syntax kind `{}`", word)),
+                    }
+                }
+                _ => return self.error("expected an argument or `)`"),
+            };
+            args.push(arg);
+            self.bump();
+        }
+        let range = TextRange::new(start, self.range().end());
+        self.bump();
+
+        use Arg::*;
+        let kind = match name.as_str() {
+            "eq?" => PredicateKind::Eq,
+            "not-eq?" => PredicateKind::NotEq,
+            "contains?" => PredicateKind::Contains,
+            "starts-with?" => PredicateKind::StartsWith,
+            "ends-with?" => PredicateKind::EndsWith,
+            "lt?" => PredicateKind::Lt,
+            "le?" => PredicateKind::Le,
+            "gt?" => PredicateKind::Gt,
+            "ge?" => PredicateKind::Ge,
+            "count-lt?" => PredicateKind::CountLt,
+            "count-eq?" => PredicateKind::CountEq,
+            "count-gt?" => PredicateKind::CountGt,
+            _ => return Err(QueryError::new(format!("unknown predicate `#{}`", name), range)),
+        };
+        let valid = match kind {
+            PredicateKind::Eq | PredicateKind::NotEq => {
+                matches!(args[..], [Capture(_), Str(_) | Capture(_)])
+            }
+            PredicateKind::Contains | PredicateKind::StartsWith | PredicateKind::EndsWith => {
+                matches!(args[..], [Capture(_), Str(_)])
+            }
+            PredicateKind::Lt | PredicateKind::Le | PredicateKind::Gt | PredicateKind::Ge => {
+                matches!(args[..], [Capture(_), Int(_)])
+            }
+            PredicateKind::CountLt | PredicateKind::CountEq | PredicateKind::CountGt => {
+                matches!(args[..], [Capture(_), Kind(_), Int(_)])
+            }
+        };
+        if !valid {
+            return Err(QueryError::new(format!("invalid arguments of `#{}`", name), range));
+        }
+        Ok(Predicate { kind, args, range })
+    }
+}
@@ -0,0 +1,266 @@
+//! Name resolution of pattern and rule references
+//!
+//! Patterns are declared in the `strings` section of a rule and they are referenced
+//! in its condition as `$a`, `#a`, `@a[1]`, `!a`, by wildcards like `($a*)` or by `them`.
+//! `resolve` finds the declaration of a single reference and `check_patterns` reports
+//! references that cannot be resolved and declarations that are never used.
+//!
+//! Rules are referenced by other rules, `RuleIndex` resolves these references
+//! and builds the `RuleGraph` of dependencies between rules.
+//! `check_duplicates` reports names that are defined more than once.
+//! `check_modules` resolves fields and functions of imported modules
+//! with the `Schema` of the modules.
+
+mod duplicates;
+mod modules;
+mod rules;
+
+pub use self::{
+    duplicates::check_duplicates,
+    modules::{check_modules, resolve_field},
+    rules::{RuleGraph, RuleIndex},
+};
+
+use crate::{
+    hir,
+    parser::suggest::similar_words,
+    syntax::ast::{self, AstNode},
+    Suggestion, SyntaxError, SyntaxKind, SyntaxNode, SyntaxToken,
+};
+
+/// Declaration of the pattern referenced by `$a`, `#a`, `@a` or `!a`
+///
+/// Returns `None` for anonymous references like `$`, wildcards like `$a*`
+/// and names that are not declared in the rule.
+pub fn resolve(token: &SyntaxToken) -> Option<ast::VariableStmt> {
+    if !is_reference(token) || is_wildcard(token) {
+        return None;
+    }
+    let name = &token.text()[1..];
+    if name.is_empty() {
+        return None;
+    }
+    let rule = token.parent_ancestors().find_map(ast::Rule::cast)?;
+    declarations(&rule).find(|stmt| stmt.variable_token().is_some_and(|it| it.text()[1..] == *name))
+}
+
+/// Check pattern references in all rules of the file
+///
+/// It reports references to undefined patterns, wildcards that match no pattern,
+/// `them` in rules without patterns, anonymous references like `$` outside of
+/// `for ... of` bodies and patterns that are never used. Patterns are used if they are
+/// referenced, matched by a wildcard or the rule uses `them`. Private patterns
+/// do not have to be used.
+///
+/// Undefined references that look like a misspelled declared name come with
+/// suggestions of the similar names.
+pub fn check_patterns(file: &ast::SourceFile) -> Vec<SyntaxError> {
+    let mut errors = Vec::new();
+    for rule in file.rules() {
+        check_rule(&rule, &mut errors);
+    }
+    errors
+}
+
+fn check_rule(rule: &ast::Rule, errors: &mut Vec<SyntaxError>) {
+    let Some(condition) = rule.body().and_then(|it| it.condition()) else {
+        return;
+    };
+    let declared: Vec<(SyntaxToken, ast::VariableStmt)> =
+        declarations(rule).filter_map(|stmt| Some((stmt.variable_token()?, stmt))).collect();
+    let mut used = vec![false; declared.len()];
+    let mut them = false;
+
+    for element in condition.syntax().descendants_with_tokens() {
+        if element.kind() == SyntaxKind::THEM_KW {
+            them = true;
+            if declared.is_empty() {
+                let message = "`them` used in a rule without patterns";
+                errors.push(SyntaxError::new(message, element.text_range()));
+            }
+            continue;
+        }
+        if let Some(wildcard) = element.as_node().cloned().and_then(ast::VariableWildcard::cast) {
+            let Some(variable) = wildcard.variable_token() else {
+                continue;
+            };
+            if wildcard.star_token().is_none() {
+                continue;
+            }
+            let mut matched = false;
+            for (i, (name, _)) in declared.iter().enumerate() {
+                if wildcard.matches(name.text()) {
+                    used[i] = true;
+                    matched = true;
+                }
+            }
+            if !matched {
+                let message = format!("no pattern matches `{}*`", variable.text());
+                errors.push(SyntaxError::new(message, wildcard.syntax().text_range()));
+            }
+            continue;
+        }
+        let Some(token) = element.into_token().filter(|it| is_reference(it) && !is_wildcard(it))
+        else {
+            continue;
+        };
+        let (sigil, name) = token.text().split_at(1);
+        if name.is_empty() {
+            if !in_for_of_body(&token) {
+                let message = format!("anonymous `{}` outside of a `for ... of` body", sigil);
+                errors.push(SyntaxError::new(message, token.text_range()));
+            }
+            continue;
+        }
+        match declared.iter().position(|(it, _)| it.text()[1..] == *name) {
+            Some(i) => used[i] = true,
+            None => {
+                let range = token.text_range();
+                let message = format!("undefined pattern `{}`", token.text());
+                let mut error = SyntaxError::new(message, range);
+                for it in similar_words(name, declared.iter().map(|(it, _)| &it.text()[1..])) {
+                    let replacement = format!("{}{}", sigil, it);
+                    let label = format!("did you mean `{}`?", replacement);
+                    error = error.with_suggestion(Suggestion::new(label, range, replacement));
+                }
+                errors.push(error);
+            }
+        }
+    }
+
+    if them {
+        return;
+    }
+    for ((name, stmt), used) in declared.iter().zip(used) {
+        let private = stmt.pattern().is_some_and(|it| it.modifiers().private.is_some());
+        if !used && !private {
+            let message = format!("unused pattern `{}`", name.text());
+            errors.push(SyntaxError::new(message, name.text_range()));
+        }
+    }
+}
+
+fn declarations(rule: &ast::Rule) -> impl Iterator<Item = ast::VariableStmt> {
+    rule.body().and_then(|it| it.strings()).into_iter().flat_map(|it| it.variable_stmts())
+}
+
+/// `$a`, `#a`, `@a` or `!a` in a condition
+fn is_reference(token: &SyntaxToken) -> bool {
+    let kind = token.kind();
+    matches!(
+        kind,
+        SyntaxKind::VARIABLE
+            | SyntaxKind::VARIABLE_COUNT
+            | SyntaxKind::VARIABLE_OFFSET
+            | SyntaxKind::VARIABLE_LENGTH
+    ) && token.parent_ancestors().any(|it| it.kind() == SyntaxKind::CONDITION)
+}
+
+/// `$a` in `$a*`
+fn is_wildcard(token: &SyntaxToken) -> bool {
+    token.parent().and_then(ast::VariableWildcard::cast).is_some_and(|it| it.star_token().is_some())
+}
+
+/// Unescaped text of a string literal, `None` if it is invalid or not UTF-8
+fn string_value(text: &str) -> Option<String> {
+    hir::unescape(text).ok().and_then(|it| String::from_utf8(it).ok())
+}
+
+/// Anonymous references refer to the pattern that is being iterated by `for ... of`
+fn in_for_of_body(token: &SyntaxToken) -> bool {
+    let is_for_of = |node: &SyntaxNode| {
+        node.kind() == SyntaxKind::FOR_EXPR
+            && node.children_with_tokens().any(|it| it.kind() == SyntaxKind::OF_KW)
+    };
+    token.parent_ancestors().any(|it| is_for_of(&it))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+    use crate::SourceFile;
+
+    fn errors(text: &str) -> Vec<String> {
+        let file = SourceFile::parse(text).tree();
+        check_patterns(&file)
+            .into_iter()
+            .map(|it| {
+                let mut res = format!("{}: {}", it.message(), &text[it.range()]);
+                let suggestions: Vec<_> =
+                    it.suggestions().iter().map(|it| it.replacement()).collect();
+                if !suggestions.is_empty() {
+                    write!(res, " {:?}", suggestions).unwrap();
+                }
+                res
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resolve_references() {
+        let text = r#"
+rule a {
+    strings:
+        $a = "a"
+        $b = "b"
+    condition:
+        $a and #b > 1 and @a[1] < !b and any of ($a*) and for any of ($b) : ($) and $c
+}"#;
+        let file = SourceFile::parse(text).tree();
+        let resolved: Vec<_> = file
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(is_reference)
+            .map(|it| {
+                let stmt = resolve(&it).map(|it| it.variable_token().unwrap().text().to_string());
+                (it.text().to_string(), stmt)
+            })
+            .collect();
+        let expected = [
+            ("$a", Some("$a")),
+            ("#b", Some("$b")),
+            ("@a", Some("$a")),
+            ("!b", Some("$b")),
+            ("$a", None),
+            ("$b", Some("$b")),
+            ("$", None),
+            ("$c", None),
+        ];
+        let expected: Vec<_> =
+            expected.iter().map(|(it, stmt)| (it.to_string(), stmt.map(str::to_string))).collect();
+        assert_eq!(resolved, expected);
+    }
+
+    #[test]
+    fn pattern_errors() {
+        let text = r#"
+rule a {
+    strings:
+        $used = "a"
+        $unused = "b"
+        $hidden = "c" private
+        $wild1 = "d"
+        $wild2 = "e"
+    condition:
+        $used and any of ($wild*) and any of ($nothing*) and $undefined and #usd > 0 and $
+}
+rule b { condition: any of them }
+rule c { strings: $a = "a" $b = "b" condition: any of them }
+rule d { strings: $a = "a" condition: for any of ($a) : (@[1] > 0) and for any i in (1..2) : (!) }"#;
+        assert_eq!(
+            errors(text),
+            [
+                "no pattern matches `$nothing*`: $nothing*",
+                "undefined pattern `$undefined`: $undefined",
+                "undefined pattern `#usd`: #usd [\"#used\"]",
+                "anonymous `$` outside of a `for ... of` body: $",
+                "unused pattern `$unused`: $unused",
+                "`them` used in a rule without patterns: them",
+                "anonymous `!` outside of a `for ... of` body: !",
+            ]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Names that are defined more than once
+
+use std::collections::HashMap;
+
+use crate::{syntax::ast, Severity, SyntaxError, SyntaxToken};
+
+/// Check duplicate rules, patterns in a rule, tags of a rule and imports in the file
+///
+/// Each duplicate is reported at its second definition with a related range
+/// pointing to the first one. Duplicate imports are only warnings, YARA accepts them.
+/// Anonymous patterns `$` can be repeated.
+pub fn check_duplicates(file: &ast::SourceFile) -> Vec<SyntaxError> {
+    let mut errors = Vec::new();
+    let imports = file.import_stmts().filter_map(|it| it.string_lit_token());
+    for error in duplicates("import", imports) {
+        errors.push(error.with_severity(Severity::Warning));
+    }
+    errors.extend(duplicates("rule", file.rules().filter_map(|it| it.identifier_token())));
+    for rule in file.rules() {
+        errors.extend(duplicates("tag", rule.tags().filter_map(|it| it.identifier_token())));
+        let patterns = rule
+            .body()
+            .and_then(|it| it.strings())
+            .into_iter()
+            .flat_map(|it| it.variable_stmts())
+            .filter_map(|it| it.variable_token())
+            .filter(|it| it.text() != "$");
+        errors.extend(duplicates("pattern", patterns));
+    }
+    errors
+}
+
+/// Report all but the first token with the same text
+fn duplicates(what: &str, tokens: impl Iterator<Item = SyntaxToken>) -> Vec<SyntaxError> {
+    let mut first = HashMap::new();
+    tokens
+        .filter_map(|token| {
+            let range = *first.entry(token.text().to_string()).or_insert(token.text_range());
+            (range != token.text_range()).then(|| {
+                let name = token.text().trim_matches('"');
+                SyntaxError::new(format!("duplicate {} `{}`", what, name), token.text_range())
+                    .with_related(range)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceFile;
+
+    #[test]
+    fn duplicate_definitions() {
+        let text = r#"
+import "pe"
+import "math"
+import "pe"
+rule a : tag1 tag2 tag1 { strings: $a = "a" $ = "b" $ = "c" $a = "d" condition: any of them }
+rule b { condition: true }
+rule a { condition: true }
+"#;
+        let file = SourceFile::parse(text).tree();
+        let errors: Vec<_> = check_duplicates(&file)
+            .into_iter()
+            .map(|it| {
+                format!(
+                    "{:?}: {}: {} (first {})",
+                    it.severity(),
+                    it.message(),
+                    &text[it.range()],
+                    u32::from(it.related().unwrap().start())
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "Warning: duplicate import `pe`: \"pe\" (first 8)",
+                "Error: duplicate rule `a`: a (first 44)",
+                "Error: duplicate tag `tag1`: tag1 (first 48)",
+                "Error: duplicate pattern `$a`: $a (first 74)",
+            ]
+        );
+    }
+}
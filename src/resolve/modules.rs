@@ -0,0 +1,453 @@
+//! Field accesses and function calls of imported modules
+//!
+//! A chain like `pe.sections[0].name` or `math.entropy(0, filesize)` is split
+//! into steps, a field, an index or a call, and the steps are followed through
+//! the `Schema` starting at the module named by the first identifier. Only chains
+//! that start with an imported module, or with a `for ... in` loop variable that
+//! iterates over an array or a dictionary of a module, are resolved. Other identifiers
+//! are rules or external variables.
+
+use super::string_value;
+use crate::{
+    parser::suggest::similar_words,
+    schema::{Constant, Member, MemberKind, Schema, Signature, Struct, Type},
+    syntax::ast::{self, AstNode},
+    Suggestion, SyntaxError, SyntaxKind, SyntaxNode, TextRange, TextSize,
+};
+
+/// Check imports, field accesses and function calls of modules against the schema
+///
+/// It reports imports of modules that are not in the schema, unknown fields
+/// and functions, indexing of values that are not arrays or dictionaries, indexing
+/// of arrays by strings and dictionaries by integers, calls
+/// of fields, functions that are not called and calls without a matching overload.
+/// Only literal arguments are compared with the parameters of the overloads.
+pub fn check_modules(file: &ast::SourceFile, schema: &Schema) -> Vec<SyntaxError> {
+    let mut errors = Vec::new();
+    for import in file.import_stmts().filter_map(|it| it.string_lit_token()) {
+        if let Some(name) = string_value(import.text()) {
+            if schema.module(&name).is_none() {
+                let message = format!("unknown module `{}`", name);
+                errors.push(SyntaxError::new(message, import.text_range()));
+            }
+        }
+    }
+    for rule in file.rules() {
+        let Some(condition) = rule.body().and_then(|it| it.condition()) else {
+            continue;
+        };
+        for root in condition.syntax().descendants().filter(is_chain_root) {
+            if let Some(chain) = Chain::new(root, file, schema) {
+                errors.extend(chain.walk(|_, _| ()));
+            }
+        }
+    }
+    errors
+}
+
+/// Schema entry of a field, function or constant named by the identifier
+///
+/// Returns `None` if the identifier is not a part of a chain that starts
+/// with an imported module, if it is the module itself or it cannot be resolved.
+pub fn resolve_field<'s>(ident: &ast::IdentifierNode, schema: &'s Schema) -> Option<&'s Member> {
+    let file = ident.syntax().ancestors().find_map(ast::SourceFile::cast)?;
+    let root = ident.syntax().ancestors().find(is_chain_root)?;
+    let chain = Chain::new(root, &file, schema)?;
+    let mut res = None;
+    chain.walk(|it, member| {
+        if it == ident {
+            res = Some(member);
+        }
+    });
+    res
+}
+
+enum Step {
+    Field(ast::IdentifierNode),
+    /// `INDEXING_EXPR`
+    Index(SyntaxNode),
+    /// `EXPR_TUPLE` of the arguments
+    Call(SyntaxNode),
+}
+
+impl Step {
+    fn end(&self) -> TextSize {
+        match self {
+            Step::Field(ident) => ident.syntax().text_range().end(),
+            Step::Index(node) | Step::Call(node) => node.text_range().end(),
+        }
+    }
+}
+
+/// Where the chain currently points in the schema
+#[derive(Clone, Copy)]
+enum Cursor<'s> {
+    Struct(&'s Struct),
+    Type(&'s Type),
+    Function(&'s [Signature]),
+}
+
+impl<'s> Cursor<'s> {
+    fn from_type(ty: &'s Type) -> Self {
+        match ty {
+            Type::Struct(it) => Cursor::Struct(it),
+            _ => Cursor::Type(ty),
+        }
+    }
+}
+
+struct Chain<'s> {
+    root: SyntaxNode,
+    steps: Vec<Step>,
+    /// What the first identifier refers to
+    start: Cursor<'s>,
+}
+
+impl<'s> Chain<'s> {
+    /// Chain of a module imported in the file or of a loop variable bound to its items
+    fn new(root: SyntaxNode, file: &ast::SourceFile, schema: &'s Schema) -> Option<Self> {
+        let mut steps = Vec::new();
+        if !chain_steps(&root, &mut steps) {
+            return None;
+        }
+        let Some(Step::Field(first)) = steps.first() else {
+            return None;
+        };
+        let name = first.identifier_token()?.text().to_string();
+        if let Some(binding) = loop_binding(first, &name) {
+            let start = Self::loop_variable(binding, &name, file, schema)?;
+            return Some(Self { root, steps, start });
+        }
+        let imported = file
+            .import_stmts()
+            .filter_map(|it| it.string_lit_token())
+            .any(|it| string_value(it.text()).is_some_and(|it| it == name));
+        if !imported {
+            return None;
+        }
+        let start = Cursor::Struct(schema.module(&name)?.members());
+        Some(Self { root, steps, start })
+    }
+
+    /// Item of the array or the key or value of the dictionary the loop iterates over
+    fn loop_variable(
+        binding: ast::ForExpr,
+        name: &str,
+        file: &ast::SourceFile,
+        schema: &'s Schema,
+    ) -> Option<Cursor<'s>> {
+        let ast::Iterable::NestedExpr(iterable) = binding.iterable()? else {
+            return None;
+        };
+        let iterable = Chain::new(iterable.syntax().first_child()?, file, schema)?;
+        let variables: Vec<_> = binding
+            .identifier_nodes()
+            .filter_map(|it| it.identifier_token())
+            .map(|it| it.text().to_string())
+            .collect();
+        let position = variables.iter().position(|it| it == name)?;
+        match (iterable.follow(|_, _| ()).ok()?, variables.len(), position) {
+            (Cursor::Type(Type::Array(item)), 1, _) => Some(Cursor::from_type(item)),
+            (Cursor::Type(Type::Dictionary(_)), 2, 0) => Some(Cursor::Type(&Type::String)),
+            (Cursor::Type(Type::Dictionary(item)), 2, _) => Some(Cursor::from_type(item)),
+            _ => None,
+        }
+    }
+
+    /// Follow the steps through the schema, `visit` is called for each resolved field
+    /// Returns the first error
+    fn walk(&self, visit: impl FnMut(&ast::IdentifierNode, &'s Member)) -> Option<SyntaxError> {
+        let cursor = match self.follow(visit) {
+            Ok(cursor) => cursor,
+            Err(error) => return Some(error),
+        };
+        if let Cursor::Function(_) = cursor {
+            let message = format!("function `{}` is not called", self.root.text());
+            return Some(SyntaxError::new(message, self.root.text_range()));
+        }
+        None
+    }
+
+    /// Follow the steps and return where the whole chain points, or the first error
+    fn follow(
+        &self,
+        mut visit: impl FnMut(&ast::IdentifierNode, &'s Member),
+    ) -> Result<Cursor<'s>, SyntaxError> {
+        let mut cursor = self.start;
+        for (i, step) in self.steps.iter().enumerate().skip(1) {
+            let prefix = self.text(self.steps[i - 1].end());
+            cursor = match (step, cursor) {
+                (Step::Field(ident), cursor) => {
+                    let name = ident.syntax().text().to_string();
+                    let member = match cursor {
+                        Cursor::Struct(it) => it.member(&name),
+                        _ => None,
+                    };
+                    let Some(member) = member else {
+                        let what = match self.steps.get(i + 1) {
+                            Some(Step::Call(_)) => "function",
+                            _ => "field",
+                        };
+                        let range = ident.syntax().text_range();
+                        let message = format!("`{}` has no {} `{}`", prefix, what, name);
+                        let mut error = SyntaxError::new(message, range);
+                        if let Cursor::Struct(it) = cursor {
+                            let names = it.members().iter().map(|it| it.name());
+                            for it in similar_words(&name, names) {
+                                let label = format!("did you mean `{}`?", it);
+                                error = error.with_suggestion(Suggestion::new(label, range, it));
+                            }
+                        }
+                        return Err(error);
+                    };
+                    visit(ident, member);
+                    match member.kind() {
+                        MemberKind::Field(ty) => Cursor::from_type(ty),
+                        MemberKind::Function(overloads) => Cursor::Function(overloads),
+                        MemberKind::Constant(Constant::Integer(_)) => Cursor::Type(&Type::Integer),
+                        MemberKind::Constant(Constant::Float(_)) => Cursor::Type(&Type::Float),
+                        MemberKind::Constant(Constant::String(_)) => Cursor::Type(&Type::String),
+                    }
+                }
+                (Step::Index(node), Cursor::Type(Type::Array(item) | Type::Dictionary(item))) => {
+                    let (key, expected) = match cursor {
+                        Cursor::Type(Type::Array(_)) => ("an integer", Type::Integer),
+                        _ => ("a string", Type::String),
+                    };
+                    let index = node.children().find(|it| it.kind() == SyntaxKind::EXPR_INDEX);
+                    if let Some(value) = index.as_ref().and_then(|it| it.first_child()) {
+                        if literal_type(&value).is_some_and(|it| it != expected) {
+                            let message = format!("`{}` must be indexed by {}", prefix, key);
+                            return Err(SyntaxError::new(message, value.text_range()));
+                        }
+                    }
+                    Cursor::from_type(item)
+                }
+                (Step::Index(node), _) => {
+                    let message = format!("`{}` cannot be indexed", prefix);
+                    return Err(SyntaxError::new(message, node.text_range()));
+                }
+                (Step::Call(args), Cursor::Function(overloads)) => {
+                    let args: Vec<_> = args.children().map(|it| literal_type(&it)).collect();
+                    let Some(overload) = overloads.iter().find(|it| accepts(it, &args)) else {
+                        let message = format!("no overload of `{}` matches the arguments", prefix);
+                        return Err(SyntaxError::new(message, self.range(i)));
+                    };
+                    Cursor::from_type(&overload.ret)
+                }
+                (Step::Call(_), _) => {
+                    let message = format!("`{}` is not a function", prefix);
+                    return Err(SyntaxError::new(message, self.range(i)));
+                }
+            };
+        }
+        Ok(cursor)
+    }
+
+    /// Text of the chain up to the offset
+    fn text(&self, end: TextSize) -> String {
+        let start = self.root.text_range().start();
+        self.root.text().slice(TextRange::new(0.into(), end - start)).to_string()
+    }
+
+    /// Range from the start of the chain to the end of the `i`-th step
+    fn range(&self, i: usize) -> TextRange {
+        TextRange::new(self.root.text_range().start(), self.steps[i].end())
+    }
+}
+
+/// Innermost `for ... in` loop whose body is in the scope of the variable `name`
+fn loop_binding(ident: &ast::IdentifierNode, name: &str) -> Option<ast::ForExpr> {
+    ident.syntax().ancestors().filter_map(ast::ForExpr::cast).find(|it| {
+        let in_body = it.iterable().is_some_and(|iterable| {
+            iterable.syntax().text_range().end() <= ident.syntax().text_range().start()
+        });
+        in_body
+            && it
+                .identifier_nodes()
+                .any(|var| var.identifier_token().is_some_and(|it| it.text() == name))
+    })
+}
+
+/// Node that starts a chain which is not a part of a longer chain
+fn is_chain_root(node: &SyntaxNode) -> bool {
+    is_chain_link(node) && !node.parent().is_some_and(|it| is_chain_link(&it))
+}
+
+fn is_chain_link(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        SyntaxKind::PRIMARY_EXPR => node.first_child().is_some_and(|it| {
+            matches!(it.kind(), SyntaxKind::FIELD_ACESS | SyntaxKind::IDENTIFIER_NODE)
+        }),
+        SyntaxKind::INDEXING_EXPR | SyntaxKind::FUNCTION_CALL_EXPR => true,
+        SyntaxKind::EXPR_BODY => has_dot(node),
+        _ => false,
+    }
+}
+
+fn has_dot(node: &SyntaxNode) -> bool {
+    node.children_with_tokens().any(|it| it.kind() == SyntaxKind::DOT)
+}
+
+/// Add the steps of the chain, returns `false` if it is not a chain
+fn chain_steps(node: &SyntaxNode, steps: &mut Vec<Step>) -> bool {
+    match node.kind() {
+        SyntaxKind::PRIMARY_EXPR => {
+            let Some(child) = node.first_child() else {
+                return false;
+            };
+            if let Some(field) = ast::FieldAcess::cast(child.clone()) {
+                steps.extend(field.identifier_nodes().map(Step::Field));
+                return true;
+            }
+            match ast::IdentifierNode::cast(child) {
+                Some(ident) => {
+                    steps.push(Step::Field(ident));
+                    true
+                }
+                None => false,
+            }
+        }
+        SyntaxKind::INDEXING_EXPR => {
+            let res = node.first_child().is_some_and(|it| chain_steps(&it, steps));
+            steps.push(Step::Index(node.clone()));
+            res
+        }
+        SyntaxKind::FUNCTION_CALL_EXPR => {
+            let res = node.first_child().is_some_and(|it| chain_steps(&it, steps));
+            match node.children().find(|it| it.kind() == SyntaxKind::EXPR_TUPLE) {
+                Some(args) => steps.push(Step::Call(args)),
+                None => return false,
+            }
+            res
+        }
+        SyntaxKind::EXPR_BODY if has_dot(node) => node.children().all(|it| chain_steps(&it, steps)),
+        _ => false,
+    }
+}
+
+/// Type of a literal argument, `None` if the argument is not a literal
+fn literal_type(node: &SyntaxNode) -> Option<Type> {
+    if node.kind() != SyntaxKind::PRIMARY_EXPR {
+        return None;
+    }
+    let token = node.first_token()?;
+    match token.kind() {
+        SyntaxKind::INT_LIT | SyntaxKind::FILESIZE_KW | SyntaxKind::ENTRYPOINT_KW => {
+            Some(Type::Integer)
+        }
+        SyntaxKind::FLOAT_LIT => Some(Type::Float),
+        SyntaxKind::STRING_LIT => Some(Type::String),
+        SyntaxKind::REGEX_LIT => Some(Type::Regex),
+        SyntaxKind::BOOL_LIT => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+/// Integers are accepted in place of floats, unknown types match any parameter
+fn accepts(signature: &Signature, args: &[Option<Type>]) -> bool {
+    signature.params.len() == args.len()
+        && signature.params.iter().zip(args).all(|(param, arg)| match arg {
+            None => true,
+            Some(Type::Integer) => matches!(param, Type::Integer | Type::Float),
+            Some(arg) => arg == param,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceFile;
+
+    #[test]
+    fn module_errors() {
+        let text = r#"
+import "pe"
+import "math"
+import "unknown"
+rule a {
+    condition:
+        pe.sections[0].name == ".text" and pe.version_info["CompanyName"] == "a" and
+        pe.sections[pe.number_of_sections - 1].nme == "a" and
+        pe.exports("a") and pe.exports(/a/) and pe.exports(1, 2) and
+        math.entropy(0, filesize) > 7.0 and math.enropy("a") > 1 and
+        pe.number_of_sections[0] and pe.machine() and pe.is_dll and
+        pe.rich_signature.version(1).x and for any s in pe.sections : (s.name == "a") and
+        for all s in pe.sections : (s.nme == "a") and for any k, v in pe.version_info : (v.x) and
+        pe.sections["a"].name == "a" and pe.version_info[0] == "a" and
+        elf.type and pe.MACHINE_I386 == 0x14c
+}"#;
+        let file = SourceFile::parse(text).tree();
+        let errors: Vec<_> = check_modules(&file, &Schema::standard())
+            .into_iter()
+            .map(|it| {
+                let suggestions: Vec<_> =
+                    it.suggestions().iter().map(|it| it.replacement().to_string()).collect();
+                format!("{}: {} {:?}", it.message(), &text[it.range()], suggestions)
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "unknown module `unknown`: \"unknown\" []",
+                "`pe.sections[pe.number_of_sections - 1]` has no field `nme`: nme [\"name\"]",
+                "no overload of `pe.exports` matches the arguments: pe.exports(1, 2) []",
+                "`math` has no function `enropy`: enropy [\"entropy\"]",
+                "`pe.number_of_sections` cannot be indexed: pe.number_of_sections[0] []",
+                "`pe.machine` is not a function: pe.machine() []",
+                "function `pe.is_dll` is not called: pe.is_dll []",
+                "`pe.rich_signature.version(1)` has no field `x`: x []",
+                "`s` has no field `nme`: nme [\"name\"]",
+                "`v` has no field `x`: x []",
+                "`pe.sections` must be indexed by an integer: \"a\" []",
+                "`pe.version_info` must be indexed by a string: 0 []",
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_fields() {
+        let text = r#"
+import "pe"
+rule a {
+    condition:
+        pe.sections[0].name == "a" and pe.exports("a") and pe.x and a.b and
+        for any s in pe.sections : (s.name == "a")
+}"#;
+        let file = SourceFile::parse(text).tree();
+        let schema = Schema::standard();
+        let resolved: Vec<_> = file
+            .syntax()
+            .descendants()
+            .filter_map(ast::IdentifierNode::cast)
+            .map(|it| {
+                let member = resolve_field(&it, &schema).map(|it| match it.kind() {
+                    MemberKind::Field(Type::Array(_)) => "array",
+                    MemberKind::Field(_) => "field",
+                    MemberKind::Function(_) => "function",
+                    MemberKind::Constant(_) => "constant",
+                });
+                format!("{}: {}", it.syntax(), member.unwrap_or("-"))
+            })
+            .collect();
+        assert_eq!(
+            resolved,
+            [
+                "pe: -",
+                "sections: array",
+                "name: field",
+                "pe: -",
+                "exports: function",
+                "pe: -",
+                "x: -",
+                "a: -",
+                "b: -",
+                "s: -",
+                "pe: -",
+                "sections: array",
+                "s: -",
+                "name: field",
+            ]
+        );
+    }
+}
@@ -0,0 +1,568 @@
+//! Rule references and the dependency graph between rules
+//!
+//! A bare identifier in a condition like `a and pe.is_dll()` refers to a rule,
+//! unless it is a loop variable of `for ... in`, an imported module, a function
+//! or an indexed array. In rule sets like `any of (a, b*)`, `b*` refers to all rules
+//! defined before whose name starts with `b`. YARA requires rules to be defined
+//! before they are referenced, so a file without errors has no dependency cycles.
+
+use std::{collections::HashMap, fmt::Write};
+
+use super::string_value;
+use crate::{
+    syntax::ast::{self, AstNode},
+    SyntaxError, SyntaxKind,
+};
+
+/// Rules of a file and the files it includes, in the order of their definitions
+#[derive(Debug, Clone)]
+pub struct RuleIndex {
+    files: Vec<ast::SourceFile>,
+    rules: Vec<Entry>,
+    /// First definition of each name
+    names: HashMap<String, usize>,
+    errors: Vec<(usize, SyntaxError)>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    rule: ast::Rule,
+    name: String,
+    file: usize,
+}
+
+/// What an identifier in a condition refers to
+enum Target {
+    Rule(usize),
+    /// `b*` in a rule set, all matching rules defined before
+    Prefix(Vec<usize>),
+    Undefined,
+}
+
+impl RuleIndex {
+    /// Index of the rules in a single file, `include` statements are ignored
+    pub fn new(file: &ast::SourceFile) -> Self {
+        Self::with_includes(file, "", |_| None)
+    }
+
+    /// Index of the rules in the file and in all files it includes
+    ///
+    /// `path` is the path of the file as other files would include it.
+    /// `load` is called for each `include` in source order with the path as written
+    /// in the source and it returns the parsed file, or `None` if it cannot be loaded.
+    /// Rules of the included file are defined at the place of the `include`.
+    /// Circular includes, including the ones back to `path`, are reported by `check`
+    /// and not followed.
+    pub fn with_includes(
+        file: &ast::SourceFile,
+        path: &str,
+        mut load: impl FnMut(&str) -> Option<ast::SourceFile>,
+    ) -> Self {
+        let mut index = Self {
+            files: Vec::new(),
+            rules: Vec::new(),
+            names: HashMap::new(),
+            errors: Vec::new(),
+        };
+        index.add_file(file.clone(), &mut load, &mut vec![path.to_string()]);
+        index
+    }
+
+    fn add_file(
+        &mut self,
+        file: ast::SourceFile,
+        load: &mut dyn FnMut(&str) -> Option<ast::SourceFile>,
+        including: &mut Vec<String>,
+    ) {
+        let id = self.files.len();
+        self.files.push(file.clone());
+        for child in file.syntax().children() {
+            if let Some(rule) = ast::Rule::cast(child.clone()) {
+                let Some(name) = rule.identifier_token() else {
+                    continue;
+                };
+                let name = name.text().to_string();
+                self.names.entry(name.clone()).or_insert(self.rules.len());
+                self.rules.push(Entry { rule, name, file: id });
+            } else if let Some(include) = ast::IncludeStmt::cast(child) {
+                let Some(path) = include.string_lit_token().and_then(|it| string_value(it.text()))
+                else {
+                    continue;
+                };
+                if including.contains(&path) {
+                    let message = format!("circular include of `{}`", path);
+                    self.errors
+                        .push((id, SyntaxError::new(message, include.syntax().text_range())));
+                    continue;
+                }
+                if let Some(included) = load(&path) {
+                    including.push(path);
+                    self.add_file(included, load, including);
+                    including.pop();
+                }
+            }
+        }
+    }
+
+    /// The indexed files, the first one is the file the index was created from
+    pub fn files(&self) -> &[ast::SourceFile] {
+        &self.files
+    }
+
+    /// All rules in the order of their definitions
+    pub fn rules(&self) -> impl Iterator<Item = &ast::Rule> {
+        self.rules.iter().map(|it| &it.rule)
+    }
+
+    /// The first rule with the name
+    pub fn rule(&self, name: &str) -> Option<&ast::Rule> {
+        self.names.get(name).map(|&it| &self.rules[it].rule)
+    }
+
+    /// Rule referenced by the identifier
+    ///
+    /// Returns `None` if the identifier is not a rule reference, for example a loop
+    /// variable or a field, or if no rule has its name. References to rules
+    /// that are defined later are resolved as well, `check` reports them.
+    pub fn resolve(&self, ident: &ast::IdentifierNode) -> Option<ast::Rule> {
+        match reference(ident)? {
+            (name, false) => self.rule(&name).cloned(),
+            (_, true) => None,
+        }
+    }
+
+    /// Report undefined references, references to rules that are defined later,
+    /// wildcards that match no rule, dependency cycles and circular includes
+    ///
+    /// Each error is paired with the index of its file in `files`.
+    /// Identifiers of external variables cannot be told apart from rules,
+    /// so they are reported as undefined rules.
+    pub fn check(&self) -> Vec<(usize, SyntaxError)> {
+        let mut errors = self.errors.clone();
+        for (i, entry) in self.rules.iter().enumerate() {
+            for (ident, target) in self.targets(i) {
+                let range = ident.syntax().text_range();
+                let text = ident.syntax().text();
+                let message = match target {
+                    // Rules referencing themselves are reported as dependency cycles
+                    Target::Rule(j) if j <= i => continue,
+                    Target::Rule(_) => format!("rule `{}` is used before its definition", text),
+                    Target::Prefix(rules) if !rules.is_empty() => continue,
+                    Target::Prefix(_) => format!("no rule matches `{}*`", text),
+                    Target::Undefined => format!("undefined rule `{}`", text),
+                };
+                errors.push((entry.file, SyntaxError::new(message, range)));
+            }
+        }
+        let graph = self.graph();
+        for cycle in graph.cycles() {
+            let entry = &self.rules[cycle[0]];
+            let message = match &cycle[..] {
+                [_] => format!("rule `{}` depends on itself", entry.name),
+                _ => {
+                    let names: Vec<_> =
+                        cycle.iter().map(|&it| format!("`{}`", self.rules[it].name)).collect();
+                    format!("dependency cycle between rules {}", names.join(", "))
+                }
+            };
+            let range = entry
+                .rule
+                .identifier_token()
+                .map_or_else(|| entry.rule.syntax().text_range(), |it| it.text_range());
+            errors.push((entry.file, SyntaxError::new(message, range)));
+        }
+        errors
+    }
+
+    /// Dependency graph of all rules
+    pub fn graph(&self) -> RuleGraph {
+        let mut dependencies = vec![Vec::new(); self.rules.len()];
+        for (i, deps) in dependencies.iter_mut().enumerate() {
+            for (_, target) in self.targets(i) {
+                match target {
+                    Target::Rule(j) => deps.push(j),
+                    Target::Prefix(rules) => deps.extend(rules),
+                    Target::Undefined => (),
+                }
+            }
+            deps.sort_unstable();
+            deps.dedup();
+        }
+        let mut dependents = vec![Vec::new(); self.rules.len()];
+        for (i, deps) in dependencies.iter().enumerate() {
+            for &j in deps {
+                dependents[j].push(i);
+            }
+        }
+        RuleGraph {
+            names: self.rules.iter().map(|it| it.name.clone()).collect(),
+            private: self.rules.iter().map(|it| is_private(&it.rule)).collect(),
+            index: self.names.clone(),
+            dependencies,
+            dependents,
+        }
+    }
+
+    /// Rule references in the condition of the `i`-th rule
+    fn targets(&self, i: usize) -> Vec<(ast::IdentifierNode, Target)> {
+        let Some(condition) = self.rules[i].rule.body().and_then(|it| it.condition()) else {
+            return Vec::new();
+        };
+        condition
+            .syntax()
+            .descendants()
+            .filter_map(ast::IdentifierNode::cast)
+            .filter_map(|ident| {
+                let target = match reference(&ident)? {
+                    (name, false) => {
+                        self.names.get(&name).map_or(Target::Undefined, |&j| Target::Rule(j))
+                    }
+                    (prefix, true) => Target::Prefix(
+                        (0..i).filter(|&j| self.rules[j].name.starts_with(&prefix)).collect(),
+                    ),
+                };
+                Some((ident, target))
+            })
+            .collect()
+    }
+}
+
+/// Dependencies between rules, a rule depends on the rules used in its condition
+///
+/// Rules are identified by their names. If a name is defined more than once,
+/// queries use its first definition.
+#[derive(Debug, Clone)]
+pub struct RuleGraph {
+    names: Vec<String>,
+    private: Vec<bool>,
+    index: HashMap<String, usize>,
+    dependencies: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+}
+
+impl RuleGraph {
+    /// Rules used directly by the rule, in the order of their definitions
+    pub fn dependencies(&self, name: &str) -> Vec<&str> {
+        self.index.get(name).map_or_else(Vec::new, |&it| self.names(&self.dependencies[it]))
+    }
+
+    /// Rules that use the rule directly, in the order of their definitions
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        self.index.get(name).map_or_else(Vec::new, |&it| self.names(&self.dependents[it]))
+    }
+
+    /// Rules that use the rule directly or through other rules,
+    /// these are the rules affected by a change of the rule
+    pub fn transitive_dependents(&self, name: &str) -> Vec<&str> {
+        let Some(&start) = self.index.get(name) else {
+            return Vec::new();
+        };
+        let mut visited = vec![false; self.names.len()];
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for &it in &self.dependents[node] {
+                if !visited[it] {
+                    visited[it] = true;
+                    stack.push(it);
+                }
+            }
+        }
+        visited[start] = false;
+        let rules: Vec<_> = (0..self.names.len()).filter(|&it| visited[it]).collect();
+        self.names(&rules)
+    }
+
+    /// All rules ordered so that each rule comes after its dependencies,
+    /// otherwise in the order of their definitions
+    ///
+    /// Returns one of the dependency cycles if there is any.
+    pub fn topological_order(&self) -> Result<Vec<&str>, Vec<&str>> {
+        if let Some(cycle) = self.cycles().first() {
+            return Err(self.names(cycle));
+        }
+        fn visit(graph: &RuleGraph, node: usize, visited: &mut [bool], order: &mut Vec<usize>) {
+            if visited[node] {
+                return;
+            }
+            visited[node] = true;
+            for &it in &graph.dependencies[node] {
+                visit(graph, it, visited, order);
+            }
+            order.push(node);
+        }
+        let mut visited = vec![false; self.names.len()];
+        let mut order = Vec::with_capacity(self.names.len());
+        for node in 0..self.names.len() {
+            visit(self, node, &mut visited, &mut order);
+        }
+        Ok(order.iter().map(|&it| self.names[it].as_str()).collect())
+    }
+
+    /// Export the graph as a Graphviz `digraph`
+    ///
+    /// Edges go from a rule to its dependencies and private rules are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut buf = String::from("digraph {\n    node [shape=box];\n");
+        for (i, name) in self.names.iter().enumerate() {
+            let style = if self.private[i] { ", style=dashed" } else { "" };
+            let _ = writeln!(buf, "    n{} [label=\"{}\"{}];", i, name, style);
+        }
+        for (i, deps) in self.dependencies.iter().enumerate() {
+            for dep in deps {
+                let _ = writeln!(buf, "    n{} -> n{};", i, dep);
+            }
+        }
+        buf.push_str("}\n");
+        buf
+    }
+
+    /// Strongly connected components with a cycle, rules in each of them are sorted
+    /// by their definitions
+    fn cycles(&self) -> Vec<Vec<usize>> {
+        struct Tarjan<'a> {
+            graph: &'a RuleGraph,
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            on_stack: Vec<bool>,
+            stack: Vec<usize>,
+            next: usize,
+            cycles: Vec<Vec<usize>>,
+        }
+
+        impl Tarjan<'_> {
+            fn visit(&mut self, node: usize) {
+                self.index[node] = Some(self.next);
+                self.low[node] = self.next;
+                self.next += 1;
+                self.stack.push(node);
+                self.on_stack[node] = true;
+                for &dep in &self.graph.dependencies[node] {
+                    match self.index[dep] {
+                        None => {
+                            self.visit(dep);
+                            self.low[node] = self.low[node].min(self.low[dep]);
+                        }
+                        Some(index) if self.on_stack[dep] => {
+                            self.low[node] = self.low[node].min(index);
+                        }
+                        Some(_) => (),
+                    }
+                }
+                if Some(self.low[node]) != self.index[node] {
+                    return;
+                }
+                let mut component = Vec::new();
+                while let Some(it) = self.stack.pop() {
+                    self.on_stack[it] = false;
+                    component.push(it);
+                    if it == node {
+                        break;
+                    }
+                }
+                if component.len() > 1 || self.graph.dependencies[node].contains(&node) {
+                    component.sort_unstable();
+                    self.cycles.push(component);
+                }
+            }
+        }
+
+        let len = self.names.len();
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: vec![None; len],
+            low: vec![0; len],
+            on_stack: vec![false; len],
+            stack: Vec::new(),
+            next: 0,
+            cycles: Vec::new(),
+        };
+        for node in 0..len {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+        tarjan.cycles.sort_unstable();
+        tarjan.cycles
+    }
+
+    fn names(&self, rules: &[usize]) -> Vec<&str> {
+        rules.iter().map(|&it| self.names[it].as_str()).collect()
+    }
+}
+
+/// Name of the referenced rule and whether it is a `name*` wildcard,
+/// `None` if the identifier is not a rule reference
+fn reference(ident: &ast::IdentifierNode) -> Option<(String, bool)> {
+    let name = ident.identifier_token()?.text().to_string();
+    let primary = ident.syntax().parent().filter(|it| it.kind() == SyntaxKind::PRIMARY_EXPR)?;
+    let parent = primary.parent()?;
+    let wildcard = match parent.kind() {
+        // `f()` and `a[0]`
+        SyntaxKind::FUNCTION_CALL_EXPR | SyntaxKind::INDEXING_EXPR => return None,
+        // A field after an index or a call, like `name` in `pe.sections[0].name`
+        SyntaxKind::EXPR_BODY
+            if parent.first_child().as_ref() != Some(&primary)
+                && parent.children_with_tokens().any(|it| it.kind() == SyntaxKind::DOT) =>
+        {
+            return None
+        }
+        SyntaxKind::EXPR_BODY => {
+            parent.children_with_tokens().any(|it| it.kind() == SyntaxKind::STAR)
+        }
+        _ => false,
+    };
+    if !ident.syntax().ancestors().any(|it| it.kind() == SyntaxKind::CONDITION) {
+        return None;
+    }
+    let is_loop_variable = ident.syntax().ancestors().filter_map(ast::ForExpr::cast).any(|it| {
+        it.identifier_nodes().any(|var| var.identifier_token().is_some_and(|it| it.text() == name))
+    });
+    if is_loop_variable {
+        return None;
+    }
+    let file = ident.syntax().ancestors().find_map(ast::SourceFile::cast)?;
+    let is_module = file
+        .import_stmts()
+        .filter_map(|it| it.string_lit_token())
+        .any(|it| string_value(it.text()).is_some_and(|it| it == name));
+    if is_module {
+        return None;
+    }
+    Some((name, wildcard))
+}
+
+fn is_private(rule: &ast::Rule) -> bool {
+    rule.modifiers().any(|it| it.private_token().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceFile;
+
+    const TEXT: &str = r#"
+import "pe"
+private rule base { condition: pe.is_dll() and filesize > 10 }
+rule uses_base { condition: base and for any i in (1..2) : (i > 0) and math.entropy(0, 1) > 1 }
+rule set_a { condition: any of (base, uses*) }
+rule top { condition: set_a and uses_base and pe.sections[0].name == "x" and pe.exports("a").x }
+"#;
+
+    fn errors(index: &RuleIndex) -> Vec<String> {
+        index
+            .check()
+            .into_iter()
+            .map(|(file, error)| {
+                let text = index.files()[file].syntax().to_string();
+                format!("{}: {}: {}", file, error.message(), &text[error.range()])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resolve_references() {
+        let file = SourceFile::parse(TEXT).tree();
+        let index = RuleIndex::new(&file);
+        assert!(errors(&index).is_empty());
+        let resolved: Vec<_> = file
+            .syntax()
+            .descendants()
+            .filter_map(ast::IdentifierNode::cast)
+            .map(|it| {
+                let rule = index.resolve(&it).and_then(|it| it.identifier_token());
+                format!("{} -> {}", it.syntax(), rule.map_or("-".to_string(), |it| it.to_string()))
+            })
+            .collect();
+        assert_eq!(
+            resolved,
+            [
+                "pe -> -",
+                "is_dll -> -",
+                "base -> base",
+                "i -> -",
+                "i -> -",
+                "math -> -",
+                "entropy -> -",
+                "base -> base",
+                "uses -> -",
+                "set_a -> set_a",
+                "uses_base -> uses_base",
+                "pe -> -",
+                "sections -> -",
+                "name -> -",
+                "pe -> -",
+                "exports -> -",
+                "x -> -",
+            ]
+        );
+    }
+
+    #[test]
+    fn includes_and_errors() {
+        let root = SourceFile::parse(
+            r#"
+include "common.yar"
+include "missing.yar"
+rule a { condition: common and b and undefined and any of (x*) }
+rule b { condition: b }
+rule c { condition: d }
+rule d { condition: c }
+"#,
+        )
+        .tree();
+        let index = RuleIndex::with_includes(&root, "root.yar", |path| match path {
+            "common.yar" => Some(
+                SourceFile::parse(
+                    "include \"common.yar\"\ninclude \"root.yar\"\nrule common { condition: true }",
+                )
+                .tree(),
+            ),
+            _ => None,
+        });
+        assert_eq!(index.files().len(), 2);
+        let names: Vec<_> =
+            index.rules().filter_map(|it| it.identifier_token()).map(|it| it.to_string()).collect();
+        assert_eq!(names, ["common", "a", "b", "c", "d"]);
+        assert_eq!(
+            errors(&index),
+            [
+                "1: circular include of `common.yar`: include \"common.yar\"",
+                "1: circular include of `root.yar`: include \"root.yar\"",
+                "0: rule `b` is used before its definition: b",
+                "0: undefined rule `undefined`: undefined",
+                "0: no rule matches `x*`: x",
+                "0: rule `d` is used before its definition: d",
+                "0: rule `b` depends on itself: b",
+                "0: dependency cycle between rules `c`, `d`: c",
+            ]
+        );
+        assert_eq!(index.graph().topological_order().unwrap_err(), ["b"]);
+    }
+
+    #[test]
+    fn dependency_graph() {
+        let index = RuleIndex::new(&SourceFile::parse(TEXT).tree());
+        let graph = index.graph();
+        assert_eq!(graph.dependencies("set_a"), ["base", "uses_base"]);
+        assert_eq!(graph.dependents("base"), ["uses_base", "set_a"]);
+        assert_eq!(graph.transitive_dependents("base"), ["uses_base", "set_a", "top"]);
+        assert!(graph.dependents("unknown").is_empty());
+        assert_eq!(graph.topological_order().unwrap(), ["base", "uses_base", "set_a", "top"]);
+        assert_eq!(
+            graph.to_dot(),
+            r#"digraph {
+    node [shape=box];
+    n0 [label="base", style=dashed];
+    n1 [label="uses_base"];
+    n2 [label="set_a"];
+    n3 [label="top"];
+    n1 -> n0;
+    n2 -> n0;
+    n2 -> n1;
+    n3 -> n1;
+    n3 -> n2;
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Schema of YARA modules like `pe`, `elf` or `math`
+//!
+//! A module is a structure of fields, functions and constants. Fields can be
+//! integers, floats, strings, booleans, arrays indexed by integers, dictionaries
+//! indexed by strings or nested structures. Functions can have more overloads
+//! and they can also take regular expressions.
+//!
+//! Schemas are written in a small text format, the standard modules are bundled
+//! with the library and returned by `Schema::standard`:
+//!
+//! ```text
+//! # Comments start with `#`
+//! module pe {
+//!     MACHINE_I386 = 0x14c                # constant
+//!     number_of_sections: integer         # field
+//!     sections: [struct {                 # array of structures
+//!         name: string
+//!         virtual_address: integer
+//!     }]
+//!     version_info: {string}              # dictionary of strings
+//!     exports(string) -> integer          # function with two overloads
+//!     exports(integer) -> integer
+//! }
+//! ```
+//!
+//! `crate::resolve::check_modules` uses the schema to check field accesses
+//! and function calls in conditions.
+
+mod parse;
+
+use crate::text_parser::TextError;
+
+/// The bundled schema of the standard modules
+const STANDARD: &str = include_str!("schema/modules.schema");
+
+/// Modules known to the schema
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    modules: Vec<Module>,
+}
+
+impl Schema {
+    /// Schema without any modules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schema of the standard modules: `pe`, `elf`, `macho`, `dotnet`, `math`, `hash`,
+    /// `time`, `console`, `string`, `magic` and `cuckoo`
+    pub fn standard() -> Self {
+        Self::parse(STANDARD).expect("the bundled schema is valid")
+    }
+
+    /// Parse the modules written in the schema format
+    pub fn parse(text: &str) -> Result<Self, SchemaError> {
+        let mut schema = Self::new();
+        schema.extend(text)?;
+        Ok(schema)
+    }
+
+    /// Add the modules written in the schema format, for example custom modules
+    ///
+    /// A module with the name of an existing module replaces it.
+    /// Nothing is added if the text is not valid.
+    pub fn extend(&mut self, text: &str) -> Result<(), SchemaError> {
+        for module in parse::parse(text)? {
+            match self.modules.iter_mut().find(|it| it.name == module.name) {
+                Some(existing) => *existing = module,
+                None => self.modules.push(module),
+            }
+        }
+        Ok(())
+    }
+
+    /// All modules in the order they were added
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    /// Module with the name used in `import`
+    pub fn module(&self, name: &str) -> Option<&Module> {
+        self.modules.iter().find(|it| it.name == name)
+    }
+}
+
+/// Module that can be imported with `import "name"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    name: String,
+    members: Struct,
+}
+
+impl Module {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Top-level fields, functions and constants
+    pub fn members(&self) -> &Struct {
+        &self.members
+    }
+}
+
+/// Structure with named members
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Struct {
+    members: Vec<Member>,
+}
+
+impl Struct {
+    /// Members in the order of their definitions
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        self.members.iter().find(|it| it.name == name)
+    }
+}
+
+/// Field, function or constant of a structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    name: String,
+    kind: MemberKind,
+}
+
+impl Member {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> &MemberKind {
+        &self.kind
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemberKind {
+    Field(Type),
+    /// Function with all its overloads
+    Function(Vec<Signature>),
+    Constant(Constant),
+}
+
+/// Parameters and the return type of a function overload
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Vec<Type>,
+    pub ret: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Integer,
+    Float,
+    String,
+    Bool,
+    /// Only used for function parameters
+    Regex,
+    /// Indexed by integers, like `pe.sections[0]`
+    Array(Box<Type>),
+    /// Indexed by strings, like `pe.version_info["CompanyName"]`
+    Dictionary(Box<Type>),
+    Struct(Struct),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Constant {
+    pub fn ty(&self) -> Type {
+        match self {
+            Constant::Integer(_) => Type::Integer,
+            Constant::Float(_) => Type::Float,
+            Constant::String(_) => Type::String,
+        }
+    }
+}
+
+/// Error in the schema text
+pub type SchemaError = TextError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_schema() {
+        let schema = Schema::parse(
+            r#"
+module test {
+    # comment
+    NAME = "test"
+    NEGATIVE = -0x10
+    RATIO = 0.5
+    count: integer
+    items: [struct { name: string tags: {bool} }]
+    has(string) -> bool
+    has(integer, float) -> bool
+}
+"#,
+        )
+        .unwrap();
+        let module = schema.module("test").unwrap();
+        let names: Vec<_> = module.members().members().iter().map(|it| it.name()).collect();
+        assert_eq!(names, ["NAME", "NEGATIVE", "RATIO", "count", "items", "has"]);
+        assert_eq!(
+            module.members().member("NEGATIVE").unwrap().kind(),
+            &MemberKind::Constant(Constant::Integer(-16))
+        );
+        let MemberKind::Field(Type::Array(item)) = module.members().member("items").unwrap().kind()
+        else {
+            panic!("expected an array");
+        };
+        let Type::Struct(item) = &**item else {
+            panic!("expected a struct");
+        };
+        assert_eq!(
+            item.member("tags").unwrap().kind(),
+            &MemberKind::Field(Type::Dictionary(Box::new(Type::Bool)))
+        );
+        let MemberKind::Function(overloads) = module.members().member("has").unwrap().kind() else {
+            panic!("expected a function");
+        };
+        assert_eq!(overloads[1].params, [Type::Integer, Type::Float]);
+    }
+
+    #[test]
+    fn schema_errors() {
+        let error = |text: &str| Schema::parse(text).unwrap_err().to_string();
+        assert_eq!(error("module"), "expected a name at 6..6");
+        assert_eq!(error("module a { b: int }"), "unknown type `int` at 14..17");
+        assert_eq!(error("module a { b: string b() -> bool }"), "duplicate member `b` at 21..22");
+        assert_eq!(error("module a { b = }"), "expected a constant at 15..16");
+        assert_eq!(error("module a { b: [string }"), "expected `]` at 22..23");
+        assert_eq!(error("module a { b = \"c"), "unterminated string at 15..17");
+        assert_eq!(error("module a { b = \"c\\"), "unterminated string at 15..18");
+        assert_eq!(error("module a { b.c: string }"), "invalid name `b.c` at 11..14");
+        assert_eq!(error("module a { b: string ! }"), "unexpected character `!` at 21..22");
+    }
+
+    #[test]
+    fn standard_and_custom_modules() {
+        let mut schema = Schema::standard();
+        for name in ["pe", "elf", "macho", "dotnet", "math", "hash", "time", "console", "string"] {
+            assert!(schema.module(name).is_some(), "{}", name);
+        }
+        let pe = schema.module("pe").unwrap().members();
+        assert!(matches!(pe.member("sections").unwrap().kind(), MemberKind::Field(Type::Array(_))));
+
+        let count = schema.modules().len();
+        schema.extend("module custom { value: integer } module math { pi: float }").unwrap();
+        assert_eq!(schema.modules().len(), count + 1);
+        assert!(schema.module("math").unwrap().members().member("entropy").is_none());
+        assert!(schema.module("custom").is_some());
+    }
+}
@@ -0,0 +1,221 @@
+//! Parser of the schema format, the tokens are parsed into modules
+
+use text_size::{TextRange, TextSize};
+
+use crate::{
+    schema::{Constant, Member, MemberKind, Module, SchemaError, Signature, Struct, Type},
+    text_parser::{self, offset, string_lit},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    /// `->`
+    Arrow,
+    /// One of `{}[]():,=`
+    Punct(char),
+}
+
+pub(super) fn parse(src: &str) -> Result<Vec<Module>, SchemaError> {
+    let tokens = tokenize(src)?;
+    let mut parser = Parser::new(tokens, src);
+    let mut modules = Vec::new();
+    while !parser.at_end() {
+        parser.keyword("module")?;
+        let (name, range) = parser.name()?;
+        if modules.iter().any(|it: &Module| it.name == name) {
+            return Err(SchemaError::new(format!("duplicate module `{}`", name), range));
+        }
+        let members = parser.struct_body()?;
+        modules.push(Module { name, members });
+    }
+    Ok(modules)
+}
+
+fn tokenize(src: &str) -> Result<Vec<(Token, TextRange)>, SchemaError> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.';
+    let mut res = Vec::new();
+    let mut chars = src.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '#' => {
+                // Comment until the end of the line
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                continue;
+            }
+            '-' if chars.next_if(|(_, c)| *c == '>').is_some() => Token::Arrow,
+            '{' | '}' | '[' | ']' | '(' | ')' | ':' | ',' | '=' => Token::Punct(c),
+            '"' => Token::Str(string_lit(&mut chars, start, src)?),
+            c if is_word(c) || c == '-' => {
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| is_word(*c)) {
+                    word.push(c);
+                }
+                let end = chars.peek().map_or(src.len(), |(it, _)| *it);
+                let range = TextRange::new(offset(start), offset(end));
+                if c.is_ascii_digit() || c == '-' {
+                    number(&word).ok_or_else(|| {
+                        SchemaError::new(format!("invalid number `{}`", word), range)
+                    })?
+                } else if word.contains('.') {
+                    return Err(SchemaError::new(format!("invalid name `{}`", word), range));
+                } else {
+                    Token::Name(word)
+                }
+            }
+            _ => {
+                let range = TextRange::at(offset(start), TextSize::of(c));
+                return Err(SchemaError::new(format!("unexpected character `{}`", c), range));
+            }
+        };
+        let end = chars.peek().map_or(src.len(), |(it, _)| *it);
+        res.push((token, TextRange::new(offset(start), offset(end))));
+    }
+    Ok(res)
+}
+
+/// Decimal or hexadecimal integer, or a float
+fn number(word: &str) -> Option<Token> {
+    let (negative, digits) = match word.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, word),
+    };
+    let sign = if negative { -1 } else { 1 };
+    if let Some(hex) = digits.strip_prefix("0x") {
+        return i64::from_str_radix(hex, 16).ok().map(|it| Token::Int(sign * it));
+    }
+    if digits.contains('.') {
+        return digits.parse::<f64>().ok().map(|it| Token::Float(sign as f64 * it));
+    }
+    digits.parse::<i64>().ok().map(|it| Token::Int(sign * it))
+}
+
+type Parser = text_parser::Parser<Token>;
+
+impl Parser {
+    fn eat(&mut self, punct: char) -> bool {
+        let res = self.peek() == Some(&Token::Punct(punct));
+        if res {
+            self.bump();
+        }
+        res
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), SchemaError> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        self.error(format!("expected `{}`", punct))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), SchemaError> {
+        match self.peek() {
+            Some(Token::Name(name)) if name == keyword => {
+                self.bump();
+                Ok(())
+            }
+            _ => self.error(format!("expected `{}`", keyword)),
+        }
+    }
+
+    fn name(&mut self) -> Result<(String, TextRange), SchemaError> {
+        let range = self.range();
+        match self.peek() {
+            Some(Token::Name(name)) => {
+                let name = name.clone();
+                self.bump();
+                Ok((name, range))
+            }
+            _ => self.error("expected a name"),
+        }
+    }
+
+    /// `{ member... }`
+    fn struct_body(&mut self) -> Result<Struct, SchemaError> {
+        self.expect('{')?;
+        let mut res = Struct::default();
+        while !self.eat('}') {
+            if self.at_end() {
+                return self.error("expected `}`");
+            }
+            let (name, range) = self.name()?;
+            let kind = match self.peek() {
+                Some(Token::Punct(':')) => {
+                    self.bump();
+                    MemberKind::Field(self.ty()?)
+                }
+                Some(Token::Punct('=')) => {
+                    self.bump();
+                    MemberKind::Constant(self.constant()?)
+                }
+                Some(Token::Punct('(')) => {
+                    self.bump();
+                    let mut params = Vec::new();
+                    while !self.eat(')') {
+                        if !params.is_empty() {
+                            self.expect(',')?;
+                        }
+                        params.push(self.ty()?);
+                    }
+                    if self.bump() != Some(Token::Arrow) {
+                        self.pos -= 1;
+                        return self.error("expected `->`");
+                    }
+                    MemberKind::Function(vec![Signature { params, ret: self.ty()? }])
+                }
+                _ => return self.error("expected `:`, `=` or `(`"),
+            };
+            match (res.members.iter_mut().find(|it| it.name == name), kind) {
+                (None, kind) => res.members.push(Member { name, kind }),
+                (
+                    Some(Member { kind: MemberKind::Function(overloads), .. }),
+                    MemberKind::Function(signature),
+                ) => overloads.extend(signature),
+                (Some(_), _) => {
+                    return Err(SchemaError::new(format!("duplicate member `{}`", name), range));
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn ty(&mut self) -> Result<Type, SchemaError> {
+        if self.eat('[') {
+            let item = self.ty()?;
+            self.expect(']')?;
+            return Ok(Type::Array(Box::new(item)));
+        }
+        if self.eat('{') {
+            let item = self.ty()?;
+            self.expect('}')?;
+            return Ok(Type::Dictionary(Box::new(item)));
+        }
+        let range = self.range();
+        let (name, _) = self.name().or_else(|_| self.error("expected a type"))?;
+        let ty = match name.as_str() {
+            "integer" => Type::Integer,
+            "float" => Type::Float,
+            "string" => Type::String,
+            "bool" => Type::Bool,
+            "regex" => Type::Regex,
+            "struct" => Type::Struct(self.struct_body()?),
+            _ => return Err(SchemaError::new(format!("unknown type `{}`", name), range)),
+        };
+        Ok(ty)
+    }
+
+    fn constant(&mut self) -> Result<Constant, SchemaError> {
+        let constant = match self.peek() {
+            Some(Token::Int(value)) => Constant::Integer(*value),
+            Some(Token::Float(value)) => Constant::Float(*value),
+            Some(Token::Str(value)) => Constant::String(value.clone()),
+            _ => return self.error("expected a constant"),
+        };
+        self.bump();
+        Ok(constant)
+    }
+}
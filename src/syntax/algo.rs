@@ -0,0 +1,170 @@
+//! Helpers for finding nodes and tokens by offsets and ranges
+//!
+//! An offset can fall inside of a token or exactly between two tokens.
+//! At the boundary, elements on both sides are considered and the innermost node wins,
+//! so the offset right after `$a` in `$a and` finds the nodes of `$a`. An offset inside whitespace
+//! or a comment finds the nodes that contain the trivia.
+
+use itertools::Itertools;
+use rowan_test::{Direction, NodeOrToken};
+use text_size::{TextRange, TextSize};
+
+use crate::syntax::{
+    ast::AstNode,
+    syntax_node::{SyntaxElement, SyntaxNode, SyntaxToken},
+};
+
+/// Ancestors of the tokens at the offset, from the innermost to the outermost
+///
+/// If the offset is between two tokens, ancestors of both are merged by the length
+/// of their ranges. It is empty if the offset is outside of `syntax`.
+pub fn ancestors_at_offset(
+    syntax: &SyntaxNode,
+    offset: TextSize,
+) -> impl Iterator<Item = SyntaxNode> {
+    let tokens: Vec<_> = if syntax.text_range().contains_inclusive(offset) {
+        syntax.token_at_offset(offset).collect()
+    } else {
+        Vec::new()
+    };
+    tokens
+        .into_iter()
+        .map(|token| token.parent_ancestors())
+        .kmerge_by(|node1, node2| node1.text_range().len() < node2.text_range().len())
+        .dedup()
+}
+
+/// Innermost node of type `N` at the offset
+///
+/// If the offset is between two nodes of type `N`, the smaller one is returned.
+pub fn find_node_at_offset<N: AstNode>(syntax: &SyntaxNode, offset: TextSize) -> Option<N> {
+    ancestors_at_offset(syntax, offset).find_map(N::cast)
+}
+
+/// Innermost node of type `N` that covers the whole range
+pub fn find_node_at_range<N: AstNode>(syntax: &SyntaxNode, range: TextRange) -> Option<N> {
+    let element = covering_element(syntax, range)?;
+    let node = match element {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent()?,
+    };
+    node.ancestors().find_map(N::cast)
+}
+
+/// Smallest node or token that covers the whole range
+///
+/// Returns `None` if the range is not inside of `syntax`.
+pub fn covering_element(syntax: &SyntaxNode, range: TextRange) -> Option<SyntaxElement> {
+    if !syntax.text_range().contains_range(range) {
+        return None;
+    }
+    Some(syntax.covering_element(range))
+}
+
+/// Closest sibling in the direction that is not whitespace or a comment
+pub fn non_trivia_sibling(element: SyntaxElement, direction: Direction) -> Option<SyntaxElement> {
+    let siblings: Box<dyn Iterator<Item = SyntaxElement>> = match element {
+        NodeOrToken::Node(node) => Box::new(node.siblings_with_tokens(direction)),
+        NodeOrToken::Token(token) => Box::new(token.siblings_with_tokens(direction)),
+    };
+    siblings.skip(1).find(|it| !it.kind().is_trivia())
+}
+
+/// Closest token before the element that is not whitespace or a comment,
+/// it does not have to be in the same parent
+pub fn previous_non_trivia_token(element: impl Into<SyntaxElement>) -> Option<SyntaxToken> {
+    let first = match element.into() {
+        NodeOrToken::Node(node) => node.first_token()?,
+        NodeOrToken::Token(token) => token,
+    };
+    let mut token = first.prev_token();
+    while let Some(current) = token {
+        if !current.kind().is_trivia() {
+            return Some(current);
+        }
+        token = current.prev_token();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{syntax::ast, SourceFile, SyntaxKind};
+
+    const TEXT: &str = r#"rule a {
+    strings:
+        $a = "foo"
+    condition:
+        $a and #a > 2
+}
+rule b { condition: true }"#;
+
+    fn offset(text: &str) -> TextSize {
+        TextSize::of(&TEXT[..TEXT.find(text).unwrap()])
+    }
+
+    #[test]
+    fn nodes_at_offset() {
+        let file = SourceFile::parse(TEXT).syntax_node();
+
+        let rule = find_node_at_offset::<ast::Rule>(&file, offset("foo")).unwrap();
+        assert_eq!(rule.identifier_token().unwrap().text(), "a");
+        let stmt = find_node_at_offset::<ast::VariableStmt>(&file, offset("foo")).unwrap();
+        assert_eq!(stmt.syntax().to_string(), r#"$a = "foo""#);
+
+        // Between `$a` and ` and`, the reference is found from the left side
+        let boundary = offset("$a and") + TextSize::of("$a");
+        let expr = find_node_at_offset::<ast::BooleanTerm>(&file, boundary).unwrap();
+        assert_eq!(expr.syntax().to_string(), "$a");
+        let kinds: Vec<_> = ancestors_at_offset(&file, boundary).map(|it| it.kind()).collect();
+        assert_eq!(kinds.first(), Some(&SyntaxKind::BOOLEAN_TERM));
+        assert_eq!(kinds.last(), Some(&SyntaxKind::SOURCE_FILE));
+
+        // Whitespace belongs to the node that contains it
+        let whitespace = offset("    condition") + TextSize::from(1);
+        assert!(find_node_at_offset::<ast::Condition>(&file, whitespace).is_none());
+        assert!(find_node_at_offset::<ast::BlockExpr>(&file, whitespace).is_some());
+
+        // Between two rules
+        let rule = find_node_at_offset::<ast::Rule>(&file, offset("rule b")).unwrap();
+        assert_eq!(rule.identifier_token().unwrap().text(), "b");
+
+        assert!(find_node_at_offset::<ast::Rule>(&file, TextSize::of(TEXT) + TextSize::from(1))
+            .is_none());
+    }
+
+    #[test]
+    fn nodes_at_range() {
+        let file = SourceFile::parse(TEXT).syntax_node();
+        let range = TextRange::at(offset("#a > 2"), TextSize::of("#a > 2"));
+        let element = covering_element(&file, range).unwrap();
+        assert_eq!(element.kind(), SyntaxKind::BOOLEAN_TERM_EXPR);
+        let expr = find_node_at_range::<ast::Expression>(&file, range).unwrap();
+        assert_eq!(expr.syntax().to_string(), "#a > 2");
+        let rule = find_node_at_range::<ast::Rule>(&file, range).unwrap();
+        assert_eq!(rule.identifier_token().unwrap().text(), "a");
+
+        let token = TextRange::at(offset("foo"), TextSize::from(1));
+        assert_eq!(covering_element(&file, token).unwrap().kind(), SyntaxKind::STRING_LIT);
+        assert!(find_node_at_range::<ast::Strings>(&file, token).is_some());
+
+        let outside = TextRange::at(TextSize::of(TEXT), TextSize::from(1));
+        assert!(covering_element(&file, outside).is_none());
+    }
+
+    #[test]
+    fn trivia_neighbours() {
+        let file = SourceFile::parse(TEXT).syntax_node();
+        let strings = file.descendants().find(|it| it.kind() == SyntaxKind::STRINGS).unwrap();
+        let next = non_trivia_sibling(strings.clone().into(), Direction::Next).unwrap();
+        assert_eq!(next.kind(), SyntaxKind::CONDITION);
+        let prev = non_trivia_sibling(strings.clone().into(), Direction::Prev).unwrap();
+        assert_eq!(prev.kind(), SyntaxKind::L_BRACE);
+
+        let token = previous_non_trivia_token(strings).unwrap();
+        assert_eq!(token.kind(), SyntaxKind::L_BRACE);
+        let first = file.first_token().unwrap();
+        assert!(previous_non_trivia_token(first).is_none());
+    }
+}
@@ -5,6 +5,11 @@ mod generated;
 mod traits;
 mod expr_ext;
 mod operators;
+mod cond;
+mod pattern;
+mod walk;
+mod edit_in_place;
+pub mod make;
 
 use std::marker::PhantomData;
 
@@ -15,9 +20,16 @@ use crate::{
 
 pub use self::{
     //expr_ext::LiteralKind,
-    generated::{nodes::*, tokens::*},
+    cond::*,
+    generated::{
+        nodes::*,
+        tokens::*,
+        visit::{fold_node, visit_node, Fold, Visitor},
+    },
     operators::*,
-    traits::HasComments,
+    pattern::*,
+    traits::{HasComments, HasModifiers, HasName, HasTags, Name},
+    walk::{fold_children, walk_node},
 };
 
 /// Zero runtime cost conversion to AST layer
@@ -100,4 +112,31 @@ pub mod support {
     pub fn token(parent: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
         parent.children_with_tokens().filter_map(|it| it.into_token()).find(|it| it.kind() == kind)
     }
+
+    /// First token of the kind before the separator, or anywhere if there is no separator
+    pub fn token_before(
+        parent: &SyntaxNode,
+        kind: SyntaxKind,
+        separator: SyntaxKind,
+    ) -> Option<SyntaxToken> {
+        parent
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .take_while(|it| it.kind() != separator)
+            .find(|it| it.kind() == kind)
+    }
+
+    /// First token of the kind after the separator, `None` if there is no separator
+    pub fn token_after(
+        parent: &SyntaxNode,
+        kind: SyntaxKind,
+        separator: SyntaxKind,
+    ) -> Option<SyntaxToken> {
+        parent
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .skip_while(|it| it.kind() != separator)
+            .skip(1)
+            .find(|it| it.kind() == kind)
+    }
 }
@@ -0,0 +1,613 @@
+//! Unified view over condition expressions
+//!
+//! The typed layer mirrors the grammar, so a single condition is spread over
+//! `Expression`, `BooleanTerm`, `BooleanTermExpr`, `Expr`, `ExprBody` and `PrimaryExpr`
+//! nodes, often wrapped into each other with only one child.
+//! `Cond` lowers all of them into one enum, wrappers are skipped and operators
+//! are resolved to `BinaryOp` and `UnaryOp`, so walking a condition needs a single `match`.
+//!
+//! Every variant keeps the syntax node it was created from, so it is always possible
+//! to get back to the concrete syntax tree (for example to report an error).
+//! Parts of the tree that are incomplete because of syntax errors are lowered to `Cond::Error`.
+
+use crate::{
+    syntax::ast::{
+        self,
+        operators::{BinaryOp, UnaryOp},
+        AstNode,
+    },
+    NodeOrToken,
+    SyntaxKind::{self, *},
+    SyntaxNode, SyntaxToken, T,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Cond {
+    /// `lhs op rhs` for logic, comparison and arithmetic operators
+    Binary { syntax: SyntaxNode, op: BinaryOp, lhs: Box<Cond>, rhs: Box<Cond> },
+    /// `not`, `defined`, `-` and `~` applied to the operand
+    Unary { syntax: SyntaxNode, op: UnaryOp, operand: Box<Cond> },
+    /// Boolean, number, string or regex literal and `filesize` or `entrypoint`
+    Literal { syntax: SyntaxNode, token: SyntaxToken, kind: LiteralKind },
+    /// Plain identifier, for example a module name or a loop variable
+    Ident { syntax: SyntaxNode, name: SyntaxToken },
+    /// `$a`, optionally anchored with `at` or `in`
+    PatternMatch { syntax: SyntaxNode, pattern: SyntaxToken, anchor: Option<Anchor> },
+    /// `#a`, optionally with `in` range
+    Count { syntax: SyntaxNode, pattern: SyntaxToken, range: Option<Box<CondRange>> },
+    /// `@a`, optionally with `[index]`
+    Offset { syntax: SyntaxNode, pattern: SyntaxToken, index: Option<Box<Cond>> },
+    /// `!a`, optionally with `[index]`
+    Length { syntax: SyntaxNode, pattern: SyntaxToken, index: Option<Box<Cond>> },
+    /// `callee(args)`
+    Call { syntax: SyntaxNode, callee: Box<Cond>, args: Vec<Cond> },
+    /// `base[index]`
+    Index { syntax: SyntaxNode, base: Box<Cond>, index: Box<Cond> },
+    /// `base.field`
+    FieldAccess { syntax: SyntaxNode, base: Box<Cond>, field: SyntaxToken },
+    /// `quantifier of set`, optionally anchored with `at` or `in`
+    Of { syntax: SyntaxNode, quantifier: QuantifierKind, set: OfSet, anchor: Option<Anchor> },
+    /// `for quantifier ... : ( body )`
+    For { syntax: SyntaxNode, quantifier: QuantifierKind, iteration: ForIteration, body: Box<Cond> },
+    /// `( inner )`
+    Paren { syntax: SyntaxNode, inner: Box<Cond> },
+    /// Expression that is not complete because of syntax errors
+    Error { syntax: SyntaxNode },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LiteralKind {
+    Bool,
+    Int,
+    Float,
+    String,
+    Regex,
+    Filesize,
+    Entrypoint,
+}
+
+/// `( lower .. upper )`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CondRange {
+    pub syntax: SyntaxNode,
+    pub lower: Cond,
+    pub upper: Cond,
+}
+
+/// Anchor of a pattern match or an `of` expression
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    /// `at offset`
+    At(Box<Cond>),
+    /// `in (lower..upper)`
+    In(Box<CondRange>),
+}
+
+/// Typed view of the `Quantifier` node
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum QuantifierKind {
+    All,
+    Any,
+    None,
+    /// `n of ...`
+    Number(Box<Cond>),
+    /// `n% of ...`
+    Percent(Box<Cond>),
+}
+
+/// Set of patterns or conditions the quantifier is applied to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OfSet {
+    /// `them`
+    Them,
+    /// `($a, $b*)`
+    Patterns(Vec<ast::VariableWildcard>),
+    /// `(cond, cond)`
+    Conds(Vec<Cond>),
+}
+
+/// What a `for` expression iterates over
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ForIteration {
+    /// `for any of ($a, $b) : (...)`
+    Patterns(OfSet),
+    /// `for any i, j in iterable : (...)`
+    Iterable { vars: Vec<SyntaxToken>, iterable: IterableKind },
+}
+
+/// Typed view of the `Iterable` node
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IterableKind {
+    /// `(lower..upper)`
+    Range(Box<CondRange>),
+    /// `(a, b, c)`
+    Tuple(Vec<Cond>),
+    /// Any other expression, for example an array or a dictionary
+    Expr(Box<Cond>),
+}
+
+impl Cond {
+    /// Lower an expression node, returns `None` if the node is not an expression
+    pub fn cast(syntax: SyntaxNode) -> Option<Cond> {
+        if !Cond::can_cast(syntax.kind()) {
+            return None;
+        }
+        Some(lower(syntax))
+    }
+
+    /// Check if a node of this kind can be lowered
+    pub fn can_cast(kind: SyntaxKind) -> bool {
+        matches!(
+            kind,
+            EXPRESSION_STMT
+                | BOOLEAN_EXPR
+                | BOOLEAN_TERM
+                | BOOLEAN_TERM_EXPR
+                | EXPR_BODY
+                | PRIMARY_EXPR
+                | NESTED_EXPR
+                | INDEXING_EXPR
+                | FUNCTION_CALL_EXPR
+                | FIELD_ACESS
+                | IDENTIFIER_NODE
+                | REGEX_PATTERN
+                | VARIABLE_COUNT
+                | VARIABLE_OFFSET
+                | VARIABLE_LENGTH
+                | OF_EXPR
+                | FOR_EXPR
+        )
+    }
+
+    pub fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Cond::Binary { syntax, .. }
+            | Cond::Unary { syntax, .. }
+            | Cond::Literal { syntax, .. }
+            | Cond::Ident { syntax, .. }
+            | Cond::PatternMatch { syntax, .. }
+            | Cond::Count { syntax, .. }
+            | Cond::Offset { syntax, .. }
+            | Cond::Length { syntax, .. }
+            | Cond::Call { syntax, .. }
+            | Cond::Index { syntax, .. }
+            | Cond::FieldAccess { syntax, .. }
+            | Cond::Of { syntax, .. }
+            | Cond::For { syntax, .. }
+            | Cond::Paren { syntax, .. }
+            | Cond::Error { syntax } => syntax,
+        }
+    }
+}
+
+impl QuantifierKind {
+    pub(super) fn lower(quantifier: &ast::Quantifier) -> Option<QuantifierKind> {
+        let node = quantifier.syntax();
+        if let Some(token) = first_token(node) {
+            match token.kind() {
+                T![all] => return Some(QuantifierKind::All),
+                T![any] => return Some(QuantifierKind::Any),
+                T![none] => return Some(QuantifierKind::None),
+                _ => (),
+            }
+        }
+        let value = Box::new(lower(node.children().next()?));
+        if tokens(node).any(|it| it.kind() == T![%]) {
+            Some(QuantifierKind::Percent(value))
+        } else {
+            Some(QuantifierKind::Number(value))
+        }
+    }
+}
+
+impl IterableKind {
+    pub(super) fn lower(iterable: &ast::Iterable) -> Option<IterableKind> {
+        let kind = match iterable {
+            ast::Iterable::Range(range) => {
+                IterableKind::Range(Box::new(CondRange::lower(range.syntax())?))
+            }
+            ast::Iterable::ExprTuple(tuple) => {
+                IterableKind::Tuple(tuple.syntax().children().map(lower).collect())
+            }
+            ast::Iterable::NestedExpr(expr) => {
+                IterableKind::Expr(Box::new(lower(expr.syntax().clone())))
+            }
+        };
+        Some(kind)
+    }
+}
+
+impl CondRange {
+    fn lower(node: &SyntaxNode) -> Option<CondRange> {
+        let mut children = node.children();
+        let lower = self::lower(children.next()?);
+        let upper = self::lower(children.next()?);
+        Some(CondRange { syntax: node.clone(), lower, upper })
+    }
+}
+
+fn lower(node: SyntaxNode) -> Cond {
+    try_lower(&node).unwrap_or(Cond::Error { syntax: node })
+}
+
+fn try_lower(node: &SyntaxNode) -> Option<Cond> {
+    let syntax = node.clone();
+    let cond = match node.kind() {
+        EXPRESSION_STMT | NESTED_EXPR => lower(node.children().next()?),
+        IDENTIFIER_NODE => Cond::Ident { syntax, name: token(node, IDENTIFIER)? },
+        FIELD_ACESS => {
+            // `a.b.c` is a flat list of identifiers, fold it into `(a.b).c`
+            let mut idents = node.children();
+            let mut cond = lower(idents.next()?);
+            for ident in idents {
+                let field = token(&ident, IDENTIFIER)?;
+                cond = Cond::FieldAccess { syntax: ident, base: Box::new(cond), field };
+            }
+            cond
+        }
+        REGEX_PATTERN => {
+            Cond::Literal { syntax, token: token(node, REGEX_LIT)?, kind: LiteralKind::Regex }
+        }
+        VARIABLE_COUNT => {
+            let range = match node.children().find(|it| it.kind() == IN_RANGE) {
+                Some(in_range) => {
+                    let range = in_range.children().find(|it| it.kind() == RANGE)?;
+                    Some(Box::new(CondRange::lower(&range)?))
+                }
+                None => None,
+            };
+            Cond::Count { syntax, pattern: token(node, VARIABLE_COUNT)?, range }
+        }
+        VARIABLE_OFFSET => {
+            let pattern = token(node, VARIABLE_OFFSET)?;
+            Cond::Offset { syntax, pattern, index: expr_index(node)? }
+        }
+        VARIABLE_LENGTH => {
+            let pattern = token(node, VARIABLE_LENGTH)?;
+            Cond::Length { syntax, pattern, index: expr_index(node)? }
+        }
+        INDEXING_EXPR => {
+            let mut children = node.children();
+            let base = Box::new(lower(children.next()?));
+            let index = children.next().filter(|it| it.kind() == EXPR_INDEX)?;
+            Cond::Index { syntax, base, index: Box::new(lower(index.children().next()?)) }
+        }
+        FUNCTION_CALL_EXPR => {
+            let mut children = node.children();
+            let callee = Box::new(lower(children.next()?));
+            let args = children.next().filter(|it| it.kind() == EXPR_TUPLE)?;
+            Cond::Call { syntax, callee, args: args.children().map(lower).collect() }
+        }
+        OF_EXPR => {
+            let quantifier = node.children().find_map(ast::Quantifier::cast)?;
+            let set = of_set(node)?;
+            let anchor = match node.children().find(|it| it.kind() == VARIABLE_ANCHOR) {
+                Some(anchor) => Some(lower_anchor(&anchor)?),
+                None => None,
+            };
+            Cond::Of { syntax, quantifier: QuantifierKind::lower(&quantifier)?, set, anchor }
+        }
+        FOR_EXPR => {
+            let quantifier = node.children().find_map(ast::Quantifier::cast)?;
+            let iteration = if tokens(node).any(|it| it.kind() == T![of]) {
+                ForIteration::Patterns(of_set(node)?)
+            } else {
+                let vars = node
+                    .children()
+                    .filter(|it| it.kind() == IDENTIFIER_NODE)
+                    .map(|it| token(&it, IDENTIFIER))
+                    .collect::<Option<_>>()?;
+                let iterable = node.children().find_map(ast::Iterable::cast)?;
+                ForIteration::Iterable { vars, iterable: IterableKind::lower(&iterable)? }
+            };
+            // The body is the only node after the colon
+            let body = node
+                .children_with_tokens()
+                .skip_while(|it| it.kind() != T![:])
+                .find_map(|it| it.into_node())?;
+            Cond::For {
+                syntax,
+                quantifier: QuantifierKind::lower(&quantifier)?,
+                iteration,
+                body: Box::new(lower(body)),
+            }
+        }
+        BOOLEAN_EXPR | BOOLEAN_TERM | BOOLEAN_TERM_EXPR | EXPR_BODY | PRIMARY_EXPR => {
+            lower_operation(node)?
+        }
+        _ => return None,
+    };
+    Some(cond)
+}
+
+/// Lower nodes that are either an operation, a literal, a pattern match,
+/// a parenthesized expression or just a wrapper around a single child
+fn lower_operation(node: &SyntaxNode) -> Option<Cond> {
+    let syntax = node.clone();
+    let mut children = node.children();
+
+    if let Some(first) = first_token(node) {
+        let unary = match first.kind() {
+            T![not] => Some(UnaryOp::Not),
+            T![defined] => Some(UnaryOp::Defined),
+            T![-] => Some(UnaryOp::Neg),
+            T![~] => Some(UnaryOp::BitNot),
+            _ => None,
+        };
+        if let Some(op) = unary {
+            return Some(Cond::Unary { syntax, op, operand: Box::new(lower(children.next()?)) });
+        }
+
+        let kind = match first.kind() {
+            BOOL_LIT => Some(LiteralKind::Bool),
+            INT_LIT => Some(LiteralKind::Int),
+            FLOAT_LIT => Some(LiteralKind::Float),
+            STRING_LIT => Some(LiteralKind::String),
+            T![filesize] => Some(LiteralKind::Filesize),
+            T![entrypoint] => Some(LiteralKind::Entrypoint),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            return Some(Cond::Literal { syntax, token: first, kind });
+        }
+
+        match first.kind() {
+            VARIABLE => {
+                let anchor = match children.find(|it| it.kind() == VARIABLE_ANCHOR) {
+                    Some(anchor) => Some(lower_anchor(&anchor)?),
+                    None => None,
+                };
+                return Some(Cond::PatternMatch { syntax, pattern: first, anchor });
+            }
+            T!['('] => {
+                return Some(Cond::Paren { syntax, inner: Box::new(lower(children.next()?)) });
+            }
+            _ => (),
+        }
+    }
+
+    let lhs = children.next()?;
+    let rhs = match children.next() {
+        Some(rhs) => rhs,
+        // Only a wrapper around a single child
+        None if tokens(node).next().is_none() => return Some(lower(lhs)),
+        None => return None,
+    };
+    let op = tokens(node).find_map(|it| BinaryOp::from_kind(it.kind()))?;
+    let rhs = lower(rhs);
+    if let (BinaryOp::ExprOp(ast::ExprOp::Dot), Cond::Ident { name, .. }) = (&op, &rhs) {
+        return Some(Cond::FieldAccess { syntax, base: Box::new(lower(lhs)), field: name.clone() });
+    }
+    Some(Cond::Binary { syntax, op, lhs: Box::new(lower(lhs)), rhs: Box::new(rhs) })
+}
+
+/// Lower `at expr` or `in range`
+fn lower_anchor(node: &SyntaxNode) -> Option<Anchor> {
+    let child = node.children().next()?;
+    match first_token(node)?.kind() {
+        T![at] => Some(Anchor::At(Box::new(lower(child)))),
+        T![in] => Some(Anchor::In(Box::new(CondRange::lower(&child)?))),
+        _ => None,
+    }
+}
+
+/// Lower `them`, a pattern tuple or a boolean expression tuple
+fn of_set(node: &SyntaxNode) -> Option<OfSet> {
+    let set = node
+        .children_with_tokens()
+        .find(|it| matches!(it.kind(), T![them] | PATTERN_IDENT_TUPLE | BOOLEAN_EXPR_TUPLE))?;
+    let set = match set {
+        NodeOrToken::Token(_) => OfSet::Them,
+        NodeOrToken::Node(tuple) if tuple.kind() == PATTERN_IDENT_TUPLE => {
+            OfSet::Patterns(tuple.children().filter_map(ast::VariableWildcard::cast).collect())
+        }
+        NodeOrToken::Node(tuple) => OfSet::Conds(tuple.children().map(lower).collect()),
+    };
+    Some(set)
+}
+
+/// Lower optional `[ expr ]` of the node
+fn expr_index(node: &SyntaxNode) -> Option<Option<Box<Cond>>> {
+    match node.children().find(|it| it.kind() == EXPR_INDEX) {
+        Some(index) => Some(Some(Box::new(lower(index.children().next()?)))),
+        None => Some(None),
+    }
+}
+
+fn tokens(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    node.children_with_tokens().filter_map(|it| it.into_token()).filter(|it| !it.kind().is_trivia())
+}
+
+/// The first non-trivia element if it is a token
+fn first_token(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.children_with_tokens().find(|it| !it.kind().is_trivia())?.into_token()
+}
+
+fn token(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    tokens(node).find(|it| it.kind() == kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{syntax::ast::AstNode, SourceFile};
+
+    fn lower_condition(text: &str) -> Cond {
+        let parse = SourceFile::parse(&format!("rule a {{ condition: {} }}", text));
+        assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+        let rule = parse.tree().rules().next().unwrap();
+        let stmt = rule.body().unwrap().condition().unwrap().expression_stmt().unwrap();
+        Cond::cast(stmt.syntax().clone()).unwrap()
+    }
+
+    /// S-expression like dump of the lowered condition
+    fn dump(cond: &Cond) -> String {
+        let dump_range =
+            |range: &CondRange| format!("{}..{}", dump(&range.lower), dump(&range.upper));
+        let dump_anchor = |anchor: &Option<Anchor>| match anchor {
+            Some(Anchor::At(at)) => format!(" at {}", dump(at)),
+            Some(Anchor::In(range)) => format!(" in {}", dump_range(range)),
+            None => String::new(),
+        };
+        let dump_all = |conds: &[Cond]| conds.iter().map(dump).collect::<Vec<_>>().join(" ");
+        let dump_quantifier = |quantifier: &QuantifierKind| match quantifier {
+            QuantifierKind::All => "all".to_string(),
+            QuantifierKind::Any => "any".to_string(),
+            QuantifierKind::None => "none".to_string(),
+            QuantifierKind::Number(n) => dump(n),
+            QuantifierKind::Percent(n) => format!("{}%", dump(n)),
+        };
+        let dump_set = |set: &OfSet| match set {
+            OfSet::Them => "them".to_string(),
+            OfSet::Patterns(patterns) => {
+                let patterns: Vec<_> = patterns.iter().map(|it| it.syntax().to_string()).collect();
+                format!("({})", patterns.join(" "))
+            }
+            OfSet::Conds(conds) => format!("({})", dump_all(conds)),
+        };
+        match cond {
+            Cond::Binary { op, lhs, rhs, .. } => format!("({} {} {})", op, dump(lhs), dump(rhs)),
+            Cond::Unary { op, operand, .. } => format!("({} {})", op, dump(operand)),
+            Cond::Literal { token, kind: LiteralKind::Regex, .. } => format!("/{}/", token.text()),
+            Cond::Literal { token, .. } => token.text().to_string(),
+            Cond::Ident { name, .. } => name.text().to_string(),
+            Cond::PatternMatch { pattern, anchor, .. } => {
+                format!("{}{}", pattern.text(), dump_anchor(anchor))
+            }
+            Cond::Count { pattern, range, .. } => match range {
+                Some(range) => format!("{} in {}", pattern.text(), dump_range(range)),
+                None => pattern.text().to_string(),
+            },
+            Cond::Offset { pattern, index, .. } | Cond::Length { pattern, index, .. } => {
+                match index {
+                    Some(index) => format!("{}[{}]", pattern.text(), dump(index)),
+                    None => pattern.text().to_string(),
+                }
+            }
+            Cond::Call { callee, args, .. } => {
+                format!("(call {} {})", dump(callee), dump_all(args))
+            }
+            Cond::Index { base, index, .. } => format!("(index {} {})", dump(base), dump(index)),
+            Cond::FieldAccess { base, field, .. } => format!("{}.{}", dump(base), field.text()),
+            Cond::Of { quantifier, set, anchor, .. } => {
+                format!(
+                    "({} of {}{})",
+                    dump_quantifier(quantifier),
+                    dump_set(set),
+                    dump_anchor(anchor)
+                )
+            }
+            Cond::For { quantifier, iteration, body, .. } => {
+                let iteration = match iteration {
+                    ForIteration::Patterns(set) => format!("of {}", dump_set(set)),
+                    ForIteration::Iterable { vars, iterable } => {
+                        let vars: Vec<_> = vars.iter().map(|it| it.text()).collect();
+                        let iterable = match iterable {
+                            IterableKind::Range(range) => dump_range(range),
+                            IterableKind::Tuple(conds) => format!("({})", dump_all(conds)),
+                            IterableKind::Expr(expr) => dump(expr),
+                        };
+                        format!("{} in {}", vars.join(" "), iterable)
+                    }
+                };
+                format!("(for {} {} {})", dump_quantifier(quantifier), iteration, dump(body))
+            }
+            Cond::Paren { inner, .. } => format!("[{}]", dump(inner)),
+            Cond::Error { .. } => "error".to_string(),
+        }
+    }
+
+    #[test]
+    fn lower_operators() {
+        let check = |text: &str, expected: &str| assert_eq!(dump(&lower_condition(text)), expected);
+
+        check("true", "true");
+        check("$a and $b or not $c", "(or (and $a $b) (not $c))");
+        check("$a and ($b or $c)", "(and $a [(or $b $c)])");
+        check("1 + 2 * 3 == 7", "(== (+ 1 (* 2 3)) 7)");
+        check("(1 + 2) * -3 < 10", "(< (* [(+ 1 2)] (- 3)) 10)");
+        check("~filesize & 0xff != 0", "(!= (& (~ filesize) 0xff) 0)");
+        check("defined foo and \"a\" contains \"b\"", "(and (defined foo) (contains \"a\" \"b\"))");
+        check("foo matches /bar/i", "(matches foo /bar/)");
+    }
+
+    #[test]
+    fn lower_patterns() {
+        let check = |text: &str, expected: &str| assert_eq!(dump(&lower_condition(text)), expected);
+
+        check("$a at 100", "$a at 100");
+        check("$a in (0..filesize)", "$a in 0..filesize");
+        check("#a in (0..100) > 2", "(> #a in 0..100 2)");
+        check("@a[1] + !a[2] == @b", "(== (+ @a[1] !a[2]) @b)");
+    }
+
+    #[test]
+    fn lower_postfix() {
+        let check = |text: &str, expected: &str| assert_eq!(dump(&lower_condition(text)), expected);
+
+        check("pe.number_of_sections == 1", "(== pe.number_of_sections 1)");
+        check("pe.sections[0].name == \"a\"", "(== (index pe.sections 0).name \"a\")");
+        check("math.entropy(0, filesize) > 7", "(> (call math.entropy 0 filesize) 7)");
+    }
+
+    #[test]
+    fn lower_quantifiers() {
+        let check = |text: &str, expected: &str| assert_eq!(dump(&lower_condition(text)), expected);
+
+        check("all of them", "(all of them)");
+        check("any of ($a, $b*) in (0..10)", "(any of ($a $b*) in 0..10)");
+        check("2 of ($a, $b)", "(2 of ($a $b))");
+        check("50% of them", "(50% of them)");
+        check("none of (true, $a)", "(none of (true $a))");
+        check("for any of ($a) : ( $ at 0 )", "(for any of ($a) $ at 0)");
+        check("for all i in (1..#a) : ( @a[i] < 10 )", "(for all i in 1..#a (< @a[i] 10))");
+        check("for any k, v in (1, 2) : ( k == v )", "(for any k v in (1 2) (== k v))");
+        check("for any s in pe.sections : ( true )", "(for any s in pe.sections true)");
+    }
+
+    #[test]
+    fn typed_quantifier_and_iterable() {
+        let cond = lower_condition("for 10% i in (1..2) : ( true )");
+        let node = cond.syntax();
+        let quantifier = node.children().find_map(ast::Quantifier::cast).unwrap();
+        assert!(matches!(quantifier.kind(), Some(QuantifierKind::Percent(_))));
+        let iterable = node.children().find_map(ast::Iterable::cast).unwrap();
+        assert!(matches!(iterable.kind(), Some(IterableKind::Range(_))));
+    }
+
+    #[test]
+    fn lower_incomplete() {
+        for text in ["rule a { condition: 1 + }", "rule a { condition: $a at }"] {
+            let parse = SourceFile::parse(text);
+            let condition =
+                parse.tree().rules().next().unwrap().body().unwrap().condition().unwrap();
+            assert_eq!(condition.cond().map(|it| dump(&it)).as_deref(), Some("error"));
+            assert!(Cond::cast(condition.syntax().clone()).is_none());
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        use ast::{BoolTermExprOp, ExprOp, LogicOp};
+
+        let ops = [
+            BinaryOp::LogicOp(LogicOp::Or),
+            BinaryOp::LogicOp(LogicOp::And),
+            BinaryOp::BoolTermExprOp(BoolTermExprOp::Eq),
+            BinaryOp::BoolTermExprOp(BoolTermExprOp::Lt),
+            BinaryOp::ExprOp(ExprOp::BitOr),
+            BinaryOp::ExprOp(ExprOp::BitXor),
+            BinaryOp::ExprOp(ExprOp::BitAnd),
+            BinaryOp::ExprOp(ExprOp::Shl),
+            BinaryOp::ExprOp(ExprOp::Add),
+            BinaryOp::ExprOp(ExprOp::Mul),
+            BinaryOp::ExprOp(ExprOp::Dot),
+        ];
+        assert!(ops.windows(2).all(|it| it[0].precedence() < it[1].precedence()));
+        assert_eq!(
+            BinaryOp::from_kind(T![matches]).map(|it| it.precedence()),
+            Some(BinaryOp::BoolTermExprOp(BoolTermExprOp::Eq).precedence())
+        );
+        assert_eq!(BinaryOp::from_kind(T![not]), None);
+    }
+}
@@ -0,0 +1,358 @@
+//! Methods for editing mutable syntax trees in place
+//!
+//! The methods can only be used on trees created by `AstNode::clone_for_update`
+//! and they panic on immutable ones. Only the edited part of the tree is changed,
+//! so the rest of the file keeps its formatting and comments.
+//! New statements are put on their own line with the indentation of the previous statement.
+
+use crate::{
+    hir::{MetaValue, RuleModifier},
+    syntax::{
+        ast::{self, expr_ext::trailing_comment, make, AstNode, AstToken, Cond},
+        syntax_node::SyntaxElement,
+    },
+    SyntaxKind, SyntaxNode,
+};
+
+impl ast::Rule {
+    /// Append the tag after the existing ones, `:` is added if the rule has no tags yet
+    ///
+    /// Returns `false` and leaves the rule unchanged if the rule has no name to put
+    /// the tags after, which can happen in rules with syntax errors.
+    pub fn add_tag(&self, name: &str) -> bool {
+        let tag = make::tag(name).clone_for_update();
+        let (anchor, mut elements): (SyntaxElement, _) = match self.tags().last() {
+            Some(last) => (last.syntax().clone().into(), Vec::new()),
+            None => match self.colon_token() {
+                Some(colon) => (colon.into(), Vec::new()),
+                None => {
+                    let Some(name) = self.identifier_token() else {
+                        return false;
+                    };
+                    (name.into(), vec![whitespace(" "), make::tokens::colon().into()])
+                }
+            },
+        };
+        elements.extend([whitespace(" "), tag.syntax().clone().into()]);
+        insert_after(&anchor, elements);
+        true
+    }
+
+    /// Add or remove the `private` modifier
+    pub fn set_private(&self, private: bool) {
+        self.set_modifier(RuleModifier::Private, private);
+    }
+
+    /// Add or remove the `global` modifier
+    pub fn set_global(&self, global: bool) {
+        self.set_modifier(RuleModifier::Global, global);
+    }
+
+    fn set_modifier(&self, modifier: RuleModifier, enabled: bool) {
+        let existing = self.modifiers().find(|it| match modifier {
+            RuleModifier::Private => it.private_token().is_some(),
+            RuleModifier::Global => it.global_token().is_some(),
+        });
+        match existing {
+            Some(existing) if !enabled => {
+                let next = existing.syntax().next_sibling_or_token();
+                if let Some(whitespace) = next.filter(|it| it.kind() == SyntaxKind::WHITESPACE) {
+                    whitespace.detach();
+                }
+                existing.syntax().detach();
+            }
+            None if enabled => {
+                // Modifiers go before the existing ones, after comments attached to the rule
+                let Some(first) =
+                    self.syntax().children_with_tokens().find(|it| !it.kind().is_trivia())
+                else {
+                    return;
+                };
+                let modifier = make::modifier(modifier).clone_for_update();
+                insert_before(&first, vec![modifier.syntax().clone().into(), whitespace(" ")]);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl ast::Meta {
+    /// Append the statement at the end of the `meta` section
+    pub fn add_stmt(&self, stmt: ast::MetaStmt) {
+        append_stmt(self.syntax(), self.meta_stmts().last().map(|it| it.syntax().clone()), stmt);
+    }
+}
+
+impl ast::MetaStmt {
+    /// Replace the value of the metadata
+    ///
+    /// Returns `false` and leaves the statement unchanged if the value cannot be
+    /// written in YARA, see `make::meta_stmt`.
+    pub fn set_value(&self, value: &MetaValue) -> bool {
+        let Some(new) = make::meta_stmt("x", value) else {
+            return false;
+        };
+        let new = new.clone_for_update();
+        let new = new.syntax().last_token().expect("metadata without a value");
+        let old = self
+            .syntax()
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != SyntaxKind::ASSIGN)
+            .skip(1)
+            .find(|it| !it.kind().is_trivia());
+        match old {
+            Some(old) => replace(&old, new.into()),
+            None => append(self.syntax(), vec![whitespace(" "), new.into()]),
+        }
+        true
+    }
+}
+
+impl ast::Strings {
+    /// Append the pattern declaration at the end of the `strings` section
+    pub fn add_pattern(&self, stmt: ast::VariableStmt) {
+        let last = self.variable_stmts().last().map(|it| it.syntax().clone());
+        append_stmt(self.syntax(), last, stmt);
+    }
+}
+
+impl ast::VariableStmt {
+    /// Rename the pattern, `name` includes the `$`
+    pub fn set_identifier(&self, name: &str) {
+        let new = make::tokens::variable(name);
+        match self.variable_token() {
+            Some(old) => replace(&old.into(), new.into()),
+            None => self.syntax().splice_children(0..0, vec![new.into(), whitespace(" ")]),
+        }
+    }
+}
+
+impl ast::PatternMod {
+    /// Remove the modifier together with the whitespace before it
+    pub fn remove(&self) {
+        let prev = self.syntax().prev_sibling_or_token();
+        if let Some(whitespace) = prev.filter(|it| it.kind() == SyntaxKind::WHITESPACE) {
+            whitespace.detach();
+        }
+        self.syntax().detach();
+    }
+}
+
+impl ast::Condition {
+    /// Replace the whole expression of the `condition` section
+    pub fn replace_expression(&self, cond: &Cond) {
+        let new = make::expression_stmt(cond).clone_for_update();
+        match self.expression_stmt() {
+            Some(old) => replace(&old.syntax().clone().into(), new.syntax().clone().into()),
+            None => append(self.syntax(), vec![whitespace(" "), new.syntax().clone().into()]),
+        }
+    }
+}
+
+/// Append the statement to the section after the `last` statement
+///
+/// The statement is put on a new line with the same indentation as the last one,
+/// or indented one level deeper than the section keyword if the section is empty.
+/// Sections written on a single line stay on a single line.
+fn append_stmt(section: &SyntaxNode, last: Option<SyntaxNode>, stmt: impl AstNode) {
+    let separator = match &last {
+        Some(last) => indent(last).map(|it| format!("\n{}", it)),
+        None => indent(section).map(|it| format!("\n{}    ", it)),
+    };
+    if let Some(comment) = last.as_ref().and_then(trailing_comment) {
+        // The comment at the end of the last line is outside of the section,
+        // move it in so the new statement is added after it and not before it
+        let comment = comment.syntax().clone();
+        if comment.parent().as_ref() != Some(section) {
+            let prev = comment.prev_token().filter(|it| it.kind() == SyntaxKind::WHITESPACE);
+            let mut elements: Vec<SyntaxElement> = prev.into_iter().map(Into::into).collect();
+            elements.push(comment.into());
+            elements.iter().for_each(SyntaxElement::detach);
+            append(section, elements);
+        }
+    }
+    let stmt = stmt.syntax().clone_subtree().clone_for_update();
+    append(section, vec![whitespace(separator.as_deref().unwrap_or(" ")), stmt.into()]);
+}
+
+/// Indentation of the line if the node is the first thing on it apart from trivia
+fn indent(node: &SyntaxNode) -> Option<String> {
+    let mut token = node.first_token()?.prev_token();
+    while let Some(current) = token {
+        match current.kind() {
+            SyntaxKind::WHITESPACE if current.text().contains('\n') => {
+                return current.text().rsplit('\n').next().map(str::to_string);
+            }
+            kind if kind.is_trivia() => token = current.prev_token(),
+            _ => return None,
+        }
+    }
+    Some(String::new())
+}
+
+fn whitespace(text: &str) -> SyntaxElement {
+    make::tokens::whitespace(text).into()
+}
+
+fn insert_before(anchor: &SyntaxElement, elements: Vec<SyntaxElement>) {
+    let index = anchor.index();
+    anchor.parent().expect("detached node").splice_children(index..index, elements);
+}
+
+fn insert_after(anchor: &SyntaxElement, elements: Vec<SyntaxElement>) {
+    let index = anchor.index() + 1;
+    anchor.parent().expect("detached node").splice_children(index..index, elements);
+}
+
+fn append(parent: &SyntaxNode, elements: Vec<SyntaxElement>) {
+    let index = parent.children_with_tokens().count();
+    parent.splice_children(index..index, elements);
+}
+
+fn replace(old: &SyntaxElement, new: SyntaxElement) {
+    let index = old.index();
+    old.parent().expect("detached node").splice_children(index..index + 1, vec![new]);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        hir::{MetaValue, PatternModifier},
+        syntax::ast::{self, make, AstNode, BinaryOp, LogicOp},
+        SourceFile,
+    };
+
+    fn rule(text: &str) -> ast::Rule {
+        let file = SourceFile::parse(text).tree().clone_for_update();
+        file.rules().next().unwrap()
+    }
+
+    #[test]
+    fn add_statements() {
+        let rule = rule(
+            r#"
+// Detects foo
+rule foo {
+    meta:
+        author = "me" // who
+    strings:
+        $a = "foo" wide
+        /* hex */ $b = { 00 01 }
+    condition:
+        $a
+}"#,
+        );
+        let body = rule.body().unwrap();
+        body.meta().unwrap().add_stmt(make::meta_stmt("score", &MetaValue::Int(50)).unwrap());
+        body.strings().unwrap().add_pattern(make::text_pattern("$c", b"bar", &[]));
+        assert_eq!(
+            rule.syntax().to_string(),
+            r#"// Detects foo
+rule foo {
+    meta:
+        author = "me" // who
+        score = 50
+    strings:
+        $a = "foo" wide
+        /* hex */ $b = { 00 01 }
+        $c = "bar"
+    condition:
+        $a
+}"#
+        );
+        let meta = body.meta().unwrap().meta_stmts().collect::<Vec<_>>();
+        assert_eq!(meta.len(), 2);
+        assert_eq!(meta[0].trailing_comment().unwrap().content(), "who");
+
+        // Single-line rules stay on a single line
+        let rule = self::rule(r#"rule a { meta: a = 1 strings: $a = "a" condition: $a }"#);
+        let body = rule.body().unwrap();
+        body.meta().unwrap().add_stmt(make::meta_stmt("b", &MetaValue::Bool(true)).unwrap());
+        body.strings().unwrap().add_pattern(make::text_pattern("$b", b"b", &[]));
+        assert_eq!(
+            rule.syntax().to_string(),
+            r#"rule a { meta: a = 1 b = true strings: $a = "a" $b = "b" condition: $a }"#
+        );
+    }
+
+    #[test]
+    fn edit_statements() {
+        let rule = rule(
+            r#"rule foo {
+    meta:
+        author = "me" // who
+    strings:
+        $a = "foo" wide xor(1-2) // the foo
+    condition:
+        $a
+}"#,
+        );
+        let body = rule.body().unwrap();
+        let meta = body.meta().unwrap().meta_stmts().next().unwrap();
+        meta.set_value(&MetaValue::String(b"\"you\"".to_vec()));
+        let pattern = body.strings().unwrap().variable_stmts().next().unwrap();
+        pattern.set_identifier("$foo");
+        for modifier in pattern.pattern().unwrap().pattern_mods() {
+            if modifier.wide_token().is_some() {
+                modifier.remove();
+            }
+        }
+        let condition = body.condition().unwrap();
+        let lhs = condition.cond().unwrap();
+        let rhs = make::of_expr(&ast::QuantifierKind::All, &[]);
+        condition.replace_expression(&make::binary(BinaryOp::LogicOp(LogicOp::And), &lhs, &rhs));
+        assert_eq!(
+            rule.syntax().to_string(),
+            r#"rule foo {
+    meta:
+        author = "\"you\"" // who
+    strings:
+        $foo = "foo" xor(1-2) // the foo
+    condition:
+        $a and all of them
+}"#
+        );
+        let modifiers = [PatternModifier::Wide];
+        let new = make::text_pattern("$x", b"x", &modifiers).clone_for_update();
+        new.pattern().unwrap().pattern_mods().next().unwrap().remove();
+        assert_eq!(new.syntax().to_string(), r#"$x = "x""#);
+    }
+
+    #[test]
+    fn edit_rule_header() {
+        let rule = rule("// doc\nrule foo {\n    condition:\n        true\n}");
+        rule.add_tag("first");
+        rule.add_tag("second");
+        rule.set_private(true);
+        rule.set_global(true);
+        rule.set_private(true);
+        assert_eq!(
+            rule.syntax().to_string(),
+            "// doc\nglobal private rule foo : first second {\n    condition:\n        true\n}"
+        );
+
+        rule.set_private(false);
+        rule.set_global(false);
+        assert_eq!(
+            rule.syntax().to_string(),
+            "// doc\nrule foo : first second {\n    condition:\n        true\n}"
+        );
+        assert!(SourceFile::parse(&rule.syntax().to_string()).errors().is_empty());
+    }
+
+    #[test]
+    fn edit_rules_with_errors() {
+        let rule = rule("rule {");
+        assert!(!rule.add_tag("tag"));
+        rule.set_global(true);
+        assert_eq!(rule.syntax().to_string(), "global rule {");
+
+        let rule = self::rule("rule a { meta: b = 1 condition: ");
+        let meta = rule.body().unwrap().meta().unwrap().meta_stmts().next().unwrap();
+        assert!(!meta.set_value(&MetaValue::Int(-1)));
+        assert!(!meta.set_value(&MetaValue::Float(f64::NAN)));
+        assert!(meta.set_value(&MetaValue::Float(1e3)));
+        assert!(rule.add_tag("tag"));
+        assert_eq!(rule.syntax().to_string(), "rule a : tag { meta: b = 1000.0 condition: ");
+    }
+}
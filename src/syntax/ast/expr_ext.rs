@@ -8,12 +8,14 @@
 //! of expressions
 
 use crate::{
+    hir::{self, Metadata},
     syntax::ast::{
         self,
         operators::{BinaryOp, BoolTermExprOp, ExprOp, LogicOp},
-        support, AstNode,
+        support, AstNode, AstToken, Cond, IterableKind, PatternKind, PatternModifiers,
+        QuantifierKind,
     },
-    SyntaxToken, T,
+    SyntaxKind, SyntaxNode, SyntaxToken, T,
 };
 
 impl ast::ExprBody {
@@ -54,26 +56,6 @@ impl ast::ExprBody {
     }
 }
 
-impl ast::XorRange {
-    pub fn lhs(&self) -> SyntaxToken {
-        self.syntax()
-            .children_with_tokens()
-            .filter(|e| !e.kind().is_trivia())
-            .nth(1)
-            .and_then(|e| e.into_token())
-            .unwrap()
-    }
-
-    pub fn rhs(&self) -> SyntaxToken {
-        self.syntax()
-            .children_with_tokens()
-            .filter(|e| !e.kind().is_trivia())
-            .nth(3)
-            .and_then(|e| e.into_token())
-            .unwrap()
-    }
-}
-
 impl ast::Range {
     pub fn lhs(&self) -> Option<ast::Expr> {
         support::children(self.syntax()).next()
@@ -84,26 +66,6 @@ impl ast::Range {
     }
 }
 
-impl ast::HexJump {
-    pub fn lhs(&self) -> SyntaxToken {
-        self.syntax()
-            .children_with_tokens()
-            .filter(|e| !e.kind().is_trivia())
-            .nth(1)
-            .and_then(|e| e.into_token())
-            .unwrap()
-    }
-
-    pub fn rhs(&self) -> SyntaxToken {
-        self.syntax()
-            .children_with_tokens()
-            .filter(|e| !e.kind().is_trivia())
-            .nth(3)
-            .and_then(|e| e.into_token())
-            .unwrap()
-    }
-}
-
 impl ast::BooleanExpr {
     pub fn op_details(&self) -> Option<(SyntaxToken, BinaryOp)> {
         self.syntax().children_with_tokens().filter_map(|it| it.into_token()).find_map(|c| {
@@ -174,6 +136,27 @@ impl ast::BooleanTermExpr {
     }
 }
 
+impl ast::Quantifier {
+    /// Typed kind of the quantifier, `None` if it is incomplete
+    pub fn kind(&self) -> Option<QuantifierKind> {
+        QuantifierKind::lower(self)
+    }
+}
+
+impl ast::Iterable {
+    /// Typed kind of the iterable, `None` if it is incomplete
+    pub fn kind(&self) -> Option<IterableKind> {
+        IterableKind::lower(self)
+    }
+}
+
+impl ast::Condition {
+    /// Unified view of the condition expression
+    pub fn cond(&self) -> Option<Cond> {
+        Cond::cast(self.expression_stmt()?.syntax().clone())
+    }
+}
+
 impl ast::VariableWildcard {
     pub fn matches(&self, ident: &str) -> bool {
         if self.star_token().is_some() {
@@ -183,3 +166,230 @@ impl ast::VariableWildcard {
         }
     }
 }
+
+impl ast::Comment {
+    /// Check if it is a `/* */` comment
+    pub fn is_block(&self) -> bool {
+        self.text().starts_with("/*")
+    }
+
+    /// Text of the comment without the `//` or `/* */` markers,
+    /// a single space after the opening marker and trailing whitespace are removed too
+    pub fn content(&self) -> &str {
+        let text = self.text();
+        let text = match text.strip_prefix("/*") {
+            Some(block) => block.strip_suffix("*/").unwrap_or(block),
+            None => text.strip_prefix("//").unwrap_or(text),
+        };
+        text.strip_prefix(' ').unwrap_or(text).trim_end()
+    }
+}
+
+impl ast::Rule {
+    /// Consecutive comments directly above the rule (and its modifiers) in source order
+    ///
+    /// The comments are found by looking at the tokens before the rule,
+    /// so the result does not depend on which comments are attached to the `RULE` node.
+    /// An empty line or a comment that ends the line of another token stops the search.
+    pub fn doc_comments(&self) -> Vec<ast::Comment> {
+        let first = self
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| !it.kind().is_trivia());
+        let mut res = Vec::new();
+        let mut token = first.and_then(|it| it.prev_token());
+        while let Some(current) = token {
+            match current.kind() {
+                SyntaxKind::WHITESPACE if current.text().matches('\n').count() > 1 => break,
+                SyntaxKind::WHITESPACE => (),
+                SyntaxKind::COMMENT if starts_line(&current) => {
+                    res.extend(ast::Comment::cast(current.clone()));
+                }
+                _ => break,
+            }
+            token = current.prev_token();
+        }
+        res.reverse();
+        res
+    }
+
+    /// Metadata of the rule with decoded values, in source order
+    /// Entries with a missing or invalid value are skipped
+    pub fn metadata(&self) -> Metadata {
+        let stmts = self.body().and_then(|it| it.meta()).into_iter().flat_map(|it| it.meta_stmts());
+        Metadata::new(stmts.filter_map(|it| hir::lower_meta(&it)).collect())
+    }
+}
+
+impl ast::Pattern {
+    /// Kind of the pattern, `None` if the value is missing or the string is invalid
+    pub fn kind(&self) -> Option<PatternKind> {
+        if let Some(token) = self.string_lit_token() {
+            return hir::unescape(token.text()).ok().map(PatternKind::Text);
+        }
+        if let Some(hex) = self.hex_pattern() {
+            return Some(PatternKind::Hex(hex));
+        }
+        self.regex_pattern().map(PatternKind::Regex)
+    }
+
+    /// Decoded modifiers of the pattern with YARA's defaults applied
+    pub fn modifiers(&self) -> PatternModifiers {
+        PatternModifiers::lower(self)
+    }
+}
+
+impl ast::VariableStmt {
+    /// Comment at the end of the line of the pattern declaration
+    pub fn trailing_comment(&self) -> Option<ast::Comment> {
+        trailing_comment(self.syntax())
+    }
+}
+
+impl ast::MetaStmt {
+    /// Comment at the end of the line of the metadata
+    pub fn trailing_comment(&self) -> Option<ast::Comment> {
+        trailing_comment(self.syntax())
+    }
+}
+
+/// Comment after the last token of the node on the same line
+pub(super) fn trailing_comment(node: &SyntaxNode) -> Option<ast::Comment> {
+    let mut token = node.last_token()?.next_token()?;
+    if token.kind() == SyntaxKind::WHITESPACE {
+        if token.text().contains('\n') {
+            return None;
+        }
+        token = token.next_token()?;
+    }
+    ast::Comment::cast(token)
+}
+
+/// Check if there is only whitespace between the start of the line and the token
+fn starts_line(token: &SyntaxToken) -> bool {
+    match token.prev_token() {
+        Some(prev) if prev.kind() == SyntaxKind::WHITESPACE => {
+            prev.text().contains('\n') || prev.prev_token().is_none()
+        }
+        Some(_) => false,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::hir::MetaValue;
+    use crate::syntax::ast::{self, AstNode};
+    use crate::SourceFile;
+
+    fn contents(comments: impl IntoIterator<Item = ast::Comment>) -> Vec<String> {
+        comments.into_iter().map(|it| it.content().to_string()).collect()
+    }
+
+    #[test]
+    fn doc_and_trailing_comments() {
+        let text = fs::read_to_string("tests/test_doc_comments.in").unwrap();
+        let parse = SourceFile::parse(&text);
+        assert!(parse.errors().is_empty());
+        let rules: Vec<_> = parse.tree().rules().collect();
+
+        assert_eq!(
+            contents(rules[0].doc_comments()),
+            ["Block doc comment", "Detects the first thing"]
+        );
+        assert!(rules[0].doc_comments()[0].is_block());
+        assert_eq!(
+            contents(rules[1].doc_comments()),
+            ["  Indented doc comment", "", "second line"]
+        );
+
+        let meta: Vec<_> =
+            rules[0].syntax().descendants().filter_map(ast::MetaStmt::cast).collect();
+        assert_eq!(contents(meta[0].trailing_comment()), ["who wrote it"]);
+        assert_eq!(meta[1].trailing_comment(), None);
+
+        let patterns: Vec<_> =
+            rules[0].syntax().descendants().filter_map(ast::VariableStmt::cast).collect();
+        assert_eq!(contents(patterns[0].trailing_comment()), ["end of line"]);
+        assert_eq!(contents(patterns[1].trailing_comment()), ["block"]);
+    }
+
+    #[test]
+    fn labelled_tokens() {
+        let text = r#"
+rule a {
+    strings:
+        $a = "a" xor(5) xor(1-3) xor(
+        $b = { 00 [-] 01 [2-] 02 [3] 03 [1-4] 04 }
+    condition:
+        $a
+}
+"#;
+        let file = SourceFile::parse(text).tree();
+        let text = |token: Option<crate::SyntaxToken>| token.map(|it| it.text().to_string());
+
+        let xor: Vec<_> = file
+            .syntax()
+            .descendants()
+            .filter_map(ast::XorRange::cast)
+            .map(|it| (text(it.lhs()), text(it.rhs())))
+            .collect();
+        let some = |it: &str| Some(it.to_string());
+        assert_eq!(xor, [(some("5"), None), (some("1"), some("3")), (None, None)]);
+
+        let jumps: Vec<_> = file
+            .syntax()
+            .descendants()
+            .filter_map(ast::HexJump::cast)
+            .map(|it| (text(it.lhs()), text(it.rhs())))
+            .collect();
+        assert_eq!(
+            jumps,
+            [(None, None), (some("2"), None), (some("3"), None), (some("1"), some("4"))]
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let text = r#"
+rule a {
+    meta:
+        author = "first \"author\""
+        version = 0x10
+        score = 1.5
+        author = "second"
+        active = false
+        broken = "\q"
+        binary = "\xff"
+    condition:
+        true
+}
+"#;
+        let rule = SourceFile::parse(text).tree().rules().next().unwrap();
+        let meta = rule.metadata();
+        assert_eq!(meta.len(), 6);
+        assert_eq!(meta.get_str("author"), Some("first \"author\""));
+        let authors: Vec<_> = meta.get_all("author").map(|it| &it.value).collect();
+        assert_eq!(
+            authors,
+            [
+                &MetaValue::String(b"first \"author\"".to_vec()),
+                &MetaValue::String(b"second".to_vec())
+            ]
+        );
+        assert_eq!(meta.get_int("version"), Some(16));
+        assert_eq!(meta.get_float("score"), Some(1.5));
+        assert_eq!(meta.get_bool("active"), Some(false));
+        assert_eq!(meta.get_int("author"), None);
+        assert_eq!(meta.get("broken"), None);
+        assert_eq!(meta.get_str("binary"), None);
+        assert_eq!(meta.get_bytes("binary"), Some(&b"\xff"[..]));
+        let entry = meta.get("score").unwrap();
+        assert_eq!(&text[entry.span], "score = 1.5");
+        let keys: Vec<_> = meta.iter().map(|it| it.key.as_str()).collect();
+        assert_eq!(keys, ["author", "version", "score", "author", "active", "binary"]);
+    }
+}
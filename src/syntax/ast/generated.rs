@@ -2,3 +2,5 @@
 pub mod nodes;
 #[rustfmt::skip]
 pub mod tokens;
+#[rustfmt::skip]
+pub mod visit;
@@ -55,6 +55,9 @@ pub struct Rule {
     pub(crate) syntax: SyntaxNode,
 }
 impl ast::HasComments for Rule {}
+impl ast::HasName for Rule {}
+impl ast::HasModifiers for Rule {}
+impl ast::HasTags for Rule {}
 impl Rule {
     pub fn modifiers(&self) -> AstChildren<Modifier> {
         support::children(&self.syntax)
@@ -176,6 +179,7 @@ impl Condition {
 pub struct MetaStmt {
     pub(crate) syntax: SyntaxNode,
 }
+impl ast::HasName for MetaStmt {}
 impl MetaStmt {
     pub fn identifier_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![identifier])
@@ -201,6 +205,7 @@ impl MetaStmt {
 pub struct VariableStmt {
     pub(crate) syntax: SyntaxNode,
 }
+impl ast::HasName for VariableStmt {}
 impl VariableStmt {
     pub fn variable_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![variable])
@@ -217,6 +222,7 @@ impl VariableStmt {
 pub struct Pattern {
     pub(crate) syntax: SyntaxNode,
 }
+impl ast::HasModifiers for Pattern {}
 impl Pattern {
     pub fn string_lit_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![string_lit])
@@ -380,9 +386,15 @@ impl HexJump {
     pub fn l_brack_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T!['['])
     }
+    pub fn lhs(&self) -> Option<SyntaxToken> {
+        support::token_before(&self.syntax, T![int_lit], T![-])
+    }
     pub fn hyphen_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![-])
     }
+    pub fn rhs(&self) -> Option<SyntaxToken> {
+        support::token_after(&self.syntax, T![int_lit], T![-])
+    }
     pub fn r_brack_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![']'])
     }
@@ -425,9 +437,15 @@ impl XorRange {
     pub fn l_paren_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T!['('])
     }
+    pub fn lhs(&self) -> Option<SyntaxToken> {
+        support::token_before(&self.syntax, T![int_lit], T![-])
+    }
     pub fn hyphen_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![-])
     }
+    pub fn rhs(&self) -> Option<SyntaxToken> {
+        support::token_after(&self.syntax, T![int_lit], T![-])
+    }
     pub fn r_paren_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![')'])
     }
@@ -718,6 +736,7 @@ impl VariableLength {
 pub struct IdentifierNode {
     pub(crate) syntax: SyntaxNode,
 }
+impl ast::HasName for IdentifierNode {}
 impl IdentifierNode {
     pub fn identifier_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![identifier])
@@ -898,6 +917,24 @@ pub struct AnyHasComments {
     pub(crate) syntax: SyntaxNode,
 }
 impl ast::HasComments for AnyHasComments {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AnyHasModifiers {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::HasModifiers for AnyHasModifiers {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AnyHasName {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::HasName for AnyHasName {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AnyHasTags {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::HasTags for AnyHasTags {}
 impl AstNode for SourceFile {
     fn can_cast(kind: SyntaxKind) -> bool {
         kind == SOURCE_FILE
@@ -1773,6 +1810,57 @@ impl AstNode for AnyHasComments {
         &self.syntax
     }
 }
+impl AnyHasModifiers {
+    #[inline]
+    pub fn new<T: ast::HasModifiers>(node: T) -> AnyHasModifiers {
+        AnyHasModifiers { syntax: node.syntax().clone() }
+    }
+}
+impl AstNode for AnyHasModifiers {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        matches!(kind, RULE | PATTERN)
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        Self::can_cast(syntax.kind()).then_some(AnyHasModifiers { syntax })
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+impl AnyHasName {
+    #[inline]
+    pub fn new<T: ast::HasName>(node: T) -> AnyHasName {
+        AnyHasName { syntax: node.syntax().clone() }
+    }
+}
+impl AstNode for AnyHasName {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        matches!(kind, RULE | META_STMT | VARIABLE_STMT | IDENTIFIER_NODE)
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        Self::can_cast(syntax.kind()).then_some(AnyHasName { syntax })
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+impl AnyHasTags {
+    #[inline]
+    pub fn new<T: ast::HasTags>(node: T) -> AnyHasTags {
+        AnyHasTags { syntax: node.syntax().clone() }
+    }
+}
+impl AstNode for AnyHasTags {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        matches!(kind, RULE)
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        Self::can_cast(syntax.kind()).then_some(AnyHasTags { syntax })
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
 impl std::fmt::Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
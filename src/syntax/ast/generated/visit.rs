@@ -0,0 +1,493 @@
+//! Generated by `sourcegen_ast`, do not edit by hand.
+
+use crate::{
+    syntax::ast::{
+        walk::{fold_children, walk_node},
+        *,
+    },
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken,
+};
+#[doc = r" Read-only traversal of the typed AST"]
+#[doc = r" Every method by default visits all children of the node in source order,"]
+#[doc = r" overridden methods can call `walk_node` to continue the traversal."]
+pub trait Visitor {
+    #[doc = r" Called for every token, including trivia"]
+    fn visit_token(&mut self, token: &SyntaxToken) {
+        let _ = token;
+    }
+    #[doc = r" Called for nodes without a typed representation, like `ERROR`"]
+    fn visit_untyped(&mut self, node: &SyntaxNode) {
+        walk_node(self, node)
+    }
+    fn visit_source_file(&mut self, node: &SourceFile) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_import_stmt(&mut self, node: &ImportStmt) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_include_stmt(&mut self, node: &IncludeStmt) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_rule(&mut self, node: &Rule) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_modifier(&mut self, node: &Modifier) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_tag(&mut self, node: &Tag) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_block_expr(&mut self, node: &BlockExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_meta(&mut self, node: &Meta) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_strings(&mut self, node: &Strings) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_condition(&mut self, node: &Condition) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_meta_stmt(&mut self, node: &MetaStmt) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_variable_stmt(&mut self, node: &VariableStmt) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_pattern(&mut self, node: &Pattern) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_pattern_mod(&mut self, node: &PatternMod) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_hex_pattern(&mut self, node: &HexPattern) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_regex_pattern(&mut self, node: &RegexPattern) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_regex_mod(&mut self, node: &RegexMod) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_hex_token(&mut self, node: &HexToken) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_hex_byte(&mut self, node: &HexByte) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_hex_alternative(&mut self, node: &HexAlternative) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_hex_token_tail(&mut self, node: &HexTokenTail) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_hex_jump(&mut self, node: &HexJump) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_hex_pipe(&mut self, node: &HexPipe) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_base_alphabet(&mut self, node: &BaseAlphabet) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_xor_range(&mut self, node: &XorRange) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_expression_stmt(&mut self, node: &ExpressionStmt) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_boolean_expr(&mut self, node: &BooleanExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_boolean_term(&mut self, node: &BooleanTerm) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_variable_anchor(&mut self, node: &VariableAnchor) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_boolean_term_expr(&mut self, node: &BooleanTermExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_of_expr(&mut self, node: &OfExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_for_expr(&mut self, node: &ForExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_primary_expr(&mut self, node: &PrimaryExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_indexing_expr(&mut self, node: &IndexingExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_function_call_expr(&mut self, node: &FunctionCallExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_expr_body(&mut self, node: &ExprBody) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_variable_count(&mut self, node: &VariableCount) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_variable_offset(&mut self, node: &VariableOffset) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_variable_length(&mut self, node: &VariableLength) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_identifier_node(&mut self, node: &IdentifierNode) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_field_acess(&mut self, node: &FieldAcess) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_in_range(&mut self, node: &InRange) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_expr_index(&mut self, node: &ExprIndex) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_range(&mut self, node: &Range) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_expr_tuple(&mut self, node: &ExprTuple) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_quantifier(&mut self, node: &Quantifier) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_pattern_ident_tuple(&mut self, node: &PatternIdentTuple) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_boolean_expr_tuple(&mut self, node: &BooleanExprTuple) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_nested_expr(&mut self, node: &NestedExpr) {
+        walk_node(self, node.syntax())
+    }
+    fn visit_variable_wildcard(&mut self, node: &VariableWildcard) {
+        walk_node(self, node.syntax())
+    }
+}
+#[doc = r" Call the `Visitor` method for the kind of the node"]
+pub fn visit_node<V: Visitor + ?Sized>(visitor: &mut V, node: &SyntaxNode) {
+    match node.kind() {
+        SOURCE_FILE => visitor.visit_source_file(&SourceFile { syntax: node.clone() }),
+        IMPORT_STMT => visitor.visit_import_stmt(&ImportStmt { syntax: node.clone() }),
+        INCLUDE_STMT => visitor.visit_include_stmt(&IncludeStmt { syntax: node.clone() }),
+        RULE => visitor.visit_rule(&Rule { syntax: node.clone() }),
+        MODIFIER => visitor.visit_modifier(&Modifier { syntax: node.clone() }),
+        TAG => visitor.visit_tag(&Tag { syntax: node.clone() }),
+        BLOCK_EXPR => visitor.visit_block_expr(&BlockExpr { syntax: node.clone() }),
+        META => visitor.visit_meta(&Meta { syntax: node.clone() }),
+        STRINGS => visitor.visit_strings(&Strings { syntax: node.clone() }),
+        CONDITION => visitor.visit_condition(&Condition { syntax: node.clone() }),
+        META_STMT => visitor.visit_meta_stmt(&MetaStmt { syntax: node.clone() }),
+        VARIABLE_STMT => visitor.visit_variable_stmt(&VariableStmt { syntax: node.clone() }),
+        PATTERN => visitor.visit_pattern(&Pattern { syntax: node.clone() }),
+        PATTERN_MOD => visitor.visit_pattern_mod(&PatternMod { syntax: node.clone() }),
+        HEX_PATTERN => visitor.visit_hex_pattern(&HexPattern { syntax: node.clone() }),
+        REGEX_PATTERN => visitor.visit_regex_pattern(&RegexPattern { syntax: node.clone() }),
+        REGEX_MOD => visitor.visit_regex_mod(&RegexMod { syntax: node.clone() }),
+        HEX_TOKEN => visitor.visit_hex_token(&HexToken { syntax: node.clone() }),
+        HEX_BYTE => visitor.visit_hex_byte(&HexByte { syntax: node.clone() }),
+        HEX_ALTERNATIVE => visitor.visit_hex_alternative(&HexAlternative { syntax: node.clone() }),
+        HEX_TOKEN_TAIL => visitor.visit_hex_token_tail(&HexTokenTail { syntax: node.clone() }),
+        HEX_JUMP => visitor.visit_hex_jump(&HexJump { syntax: node.clone() }),
+        HEX_PIPE => visitor.visit_hex_pipe(&HexPipe { syntax: node.clone() }),
+        BASE_ALPHABET => visitor.visit_base_alphabet(&BaseAlphabet { syntax: node.clone() }),
+        XOR_RANGE => visitor.visit_xor_range(&XorRange { syntax: node.clone() }),
+        EXPRESSION_STMT => visitor.visit_expression_stmt(&ExpressionStmt { syntax: node.clone() }),
+        BOOLEAN_EXPR => visitor.visit_boolean_expr(&BooleanExpr { syntax: node.clone() }),
+        BOOLEAN_TERM => visitor.visit_boolean_term(&BooleanTerm { syntax: node.clone() }),
+        VARIABLE_ANCHOR => visitor.visit_variable_anchor(&VariableAnchor { syntax: node.clone() }),
+        BOOLEAN_TERM_EXPR => {
+            visitor.visit_boolean_term_expr(&BooleanTermExpr { syntax: node.clone() })
+        }
+        OF_EXPR => visitor.visit_of_expr(&OfExpr { syntax: node.clone() }),
+        FOR_EXPR => visitor.visit_for_expr(&ForExpr { syntax: node.clone() }),
+        PRIMARY_EXPR => visitor.visit_primary_expr(&PrimaryExpr { syntax: node.clone() }),
+        INDEXING_EXPR => visitor.visit_indexing_expr(&IndexingExpr { syntax: node.clone() }),
+        FUNCTION_CALL_EXPR => {
+            visitor.visit_function_call_expr(&FunctionCallExpr { syntax: node.clone() })
+        }
+        EXPR_BODY => visitor.visit_expr_body(&ExprBody { syntax: node.clone() }),
+        VARIABLE_COUNT => visitor.visit_variable_count(&VariableCount { syntax: node.clone() }),
+        VARIABLE_OFFSET => visitor.visit_variable_offset(&VariableOffset { syntax: node.clone() }),
+        VARIABLE_LENGTH => visitor.visit_variable_length(&VariableLength { syntax: node.clone() }),
+        IDENTIFIER_NODE => visitor.visit_identifier_node(&IdentifierNode { syntax: node.clone() }),
+        FIELD_ACESS => visitor.visit_field_acess(&FieldAcess { syntax: node.clone() }),
+        IN_RANGE => visitor.visit_in_range(&InRange { syntax: node.clone() }),
+        EXPR_INDEX => visitor.visit_expr_index(&ExprIndex { syntax: node.clone() }),
+        RANGE => visitor.visit_range(&Range { syntax: node.clone() }),
+        EXPR_TUPLE => visitor.visit_expr_tuple(&ExprTuple { syntax: node.clone() }),
+        QUANTIFIER => visitor.visit_quantifier(&Quantifier { syntax: node.clone() }),
+        PATTERN_IDENT_TUPLE => {
+            visitor.visit_pattern_ident_tuple(&PatternIdentTuple { syntax: node.clone() })
+        }
+        BOOLEAN_EXPR_TUPLE => {
+            visitor.visit_boolean_expr_tuple(&BooleanExprTuple { syntax: node.clone() })
+        }
+        NESTED_EXPR => visitor.visit_nested_expr(&NestedExpr { syntax: node.clone() }),
+        VARIABLE_WILDCARD => {
+            visitor.visit_variable_wildcard(&VariableWildcard { syntax: node.clone() })
+        }
+        _ => visitor.visit_untyped(node),
+    }
+}
+#[doc = r" Rewriting traversal of the typed AST"]
+#[doc = r" Every method by default folds all children of the node and rebuilds it"]
+#[doc = r" if any of them changed. Returning `None` removes the node from its parent."]
+#[doc = r" Rebuilt nodes are detached from the original tree."]
+pub trait Fold {
+    #[doc = r" Called for every token, including trivia"]
+    fn fold_token(&mut self, token: SyntaxToken) -> Option<SyntaxToken> {
+        Some(token)
+    }
+    #[doc = r" Called for nodes without a typed representation, like `ERROR`"]
+    fn fold_untyped(&mut self, node: SyntaxNode) -> Option<SyntaxNode> {
+        Some(fold_children(self, node))
+    }
+    fn fold_source_file(&mut self, node: SourceFile) -> Option<SourceFile> {
+        Some(SourceFile { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_import_stmt(&mut self, node: ImportStmt) -> Option<ImportStmt> {
+        Some(ImportStmt { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_include_stmt(&mut self, node: IncludeStmt) -> Option<IncludeStmt> {
+        Some(IncludeStmt { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_rule(&mut self, node: Rule) -> Option<Rule> {
+        Some(Rule { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_modifier(&mut self, node: Modifier) -> Option<Modifier> {
+        Some(Modifier { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_tag(&mut self, node: Tag) -> Option<Tag> {
+        Some(Tag { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_block_expr(&mut self, node: BlockExpr) -> Option<BlockExpr> {
+        Some(BlockExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_meta(&mut self, node: Meta) -> Option<Meta> {
+        Some(Meta { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_strings(&mut self, node: Strings) -> Option<Strings> {
+        Some(Strings { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_condition(&mut self, node: Condition) -> Option<Condition> {
+        Some(Condition { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_meta_stmt(&mut self, node: MetaStmt) -> Option<MetaStmt> {
+        Some(MetaStmt { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_variable_stmt(&mut self, node: VariableStmt) -> Option<VariableStmt> {
+        Some(VariableStmt { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_pattern(&mut self, node: Pattern) -> Option<Pattern> {
+        Some(Pattern { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_pattern_mod(&mut self, node: PatternMod) -> Option<PatternMod> {
+        Some(PatternMod { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_hex_pattern(&mut self, node: HexPattern) -> Option<HexPattern> {
+        Some(HexPattern { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_regex_pattern(&mut self, node: RegexPattern) -> Option<RegexPattern> {
+        Some(RegexPattern { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_regex_mod(&mut self, node: RegexMod) -> Option<RegexMod> {
+        Some(RegexMod { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_hex_token(&mut self, node: HexToken) -> Option<HexToken> {
+        Some(HexToken { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_hex_byte(&mut self, node: HexByte) -> Option<HexByte> {
+        Some(HexByte { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_hex_alternative(&mut self, node: HexAlternative) -> Option<HexAlternative> {
+        Some(HexAlternative { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_hex_token_tail(&mut self, node: HexTokenTail) -> Option<HexTokenTail> {
+        Some(HexTokenTail { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_hex_jump(&mut self, node: HexJump) -> Option<HexJump> {
+        Some(HexJump { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_hex_pipe(&mut self, node: HexPipe) -> Option<HexPipe> {
+        Some(HexPipe { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_base_alphabet(&mut self, node: BaseAlphabet) -> Option<BaseAlphabet> {
+        Some(BaseAlphabet { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_xor_range(&mut self, node: XorRange) -> Option<XorRange> {
+        Some(XorRange { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_expression_stmt(&mut self, node: ExpressionStmt) -> Option<ExpressionStmt> {
+        Some(ExpressionStmt { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_boolean_expr(&mut self, node: BooleanExpr) -> Option<BooleanExpr> {
+        Some(BooleanExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_boolean_term(&mut self, node: BooleanTerm) -> Option<BooleanTerm> {
+        Some(BooleanTerm { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_variable_anchor(&mut self, node: VariableAnchor) -> Option<VariableAnchor> {
+        Some(VariableAnchor { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_boolean_term_expr(&mut self, node: BooleanTermExpr) -> Option<BooleanTermExpr> {
+        Some(BooleanTermExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_of_expr(&mut self, node: OfExpr) -> Option<OfExpr> {
+        Some(OfExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_for_expr(&mut self, node: ForExpr) -> Option<ForExpr> {
+        Some(ForExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_primary_expr(&mut self, node: PrimaryExpr) -> Option<PrimaryExpr> {
+        Some(PrimaryExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_indexing_expr(&mut self, node: IndexingExpr) -> Option<IndexingExpr> {
+        Some(IndexingExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_function_call_expr(&mut self, node: FunctionCallExpr) -> Option<FunctionCallExpr> {
+        Some(FunctionCallExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_expr_body(&mut self, node: ExprBody) -> Option<ExprBody> {
+        Some(ExprBody { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_variable_count(&mut self, node: VariableCount) -> Option<VariableCount> {
+        Some(VariableCount { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_variable_offset(&mut self, node: VariableOffset) -> Option<VariableOffset> {
+        Some(VariableOffset { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_variable_length(&mut self, node: VariableLength) -> Option<VariableLength> {
+        Some(VariableLength { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_identifier_node(&mut self, node: IdentifierNode) -> Option<IdentifierNode> {
+        Some(IdentifierNode { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_field_acess(&mut self, node: FieldAcess) -> Option<FieldAcess> {
+        Some(FieldAcess { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_in_range(&mut self, node: InRange) -> Option<InRange> {
+        Some(InRange { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_expr_index(&mut self, node: ExprIndex) -> Option<ExprIndex> {
+        Some(ExprIndex { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_range(&mut self, node: Range) -> Option<Range> {
+        Some(Range { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_expr_tuple(&mut self, node: ExprTuple) -> Option<ExprTuple> {
+        Some(ExprTuple { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_quantifier(&mut self, node: Quantifier) -> Option<Quantifier> {
+        Some(Quantifier { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_pattern_ident_tuple(&mut self, node: PatternIdentTuple) -> Option<PatternIdentTuple> {
+        Some(PatternIdentTuple { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_boolean_expr_tuple(&mut self, node: BooleanExprTuple) -> Option<BooleanExprTuple> {
+        Some(BooleanExprTuple { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_nested_expr(&mut self, node: NestedExpr) -> Option<NestedExpr> {
+        Some(NestedExpr { syntax: fold_children(self, node.syntax) })
+    }
+    fn fold_variable_wildcard(&mut self, node: VariableWildcard) -> Option<VariableWildcard> {
+        Some(VariableWildcard { syntax: fold_children(self, node.syntax) })
+    }
+}
+#[doc = r" Call the `Fold` method for the kind of the node"]
+pub fn fold_node<F: Fold + ?Sized>(folder: &mut F, node: SyntaxNode) -> Option<SyntaxNode> {
+    match node.kind() {
+        SOURCE_FILE => folder.fold_source_file(SourceFile { syntax: node }).map(|it| it.syntax),
+        IMPORT_STMT => folder.fold_import_stmt(ImportStmt { syntax: node }).map(|it| it.syntax),
+        INCLUDE_STMT => folder.fold_include_stmt(IncludeStmt { syntax: node }).map(|it| it.syntax),
+        RULE => folder.fold_rule(Rule { syntax: node }).map(|it| it.syntax),
+        MODIFIER => folder.fold_modifier(Modifier { syntax: node }).map(|it| it.syntax),
+        TAG => folder.fold_tag(Tag { syntax: node }).map(|it| it.syntax),
+        BLOCK_EXPR => folder.fold_block_expr(BlockExpr { syntax: node }).map(|it| it.syntax),
+        META => folder.fold_meta(Meta { syntax: node }).map(|it| it.syntax),
+        STRINGS => folder.fold_strings(Strings { syntax: node }).map(|it| it.syntax),
+        CONDITION => folder.fold_condition(Condition { syntax: node }).map(|it| it.syntax),
+        META_STMT => folder.fold_meta_stmt(MetaStmt { syntax: node }).map(|it| it.syntax),
+        VARIABLE_STMT => {
+            folder.fold_variable_stmt(VariableStmt { syntax: node }).map(|it| it.syntax)
+        }
+        PATTERN => folder.fold_pattern(Pattern { syntax: node }).map(|it| it.syntax),
+        PATTERN_MOD => folder.fold_pattern_mod(PatternMod { syntax: node }).map(|it| it.syntax),
+        HEX_PATTERN => folder.fold_hex_pattern(HexPattern { syntax: node }).map(|it| it.syntax),
+        REGEX_PATTERN => {
+            folder.fold_regex_pattern(RegexPattern { syntax: node }).map(|it| it.syntax)
+        }
+        REGEX_MOD => folder.fold_regex_mod(RegexMod { syntax: node }).map(|it| it.syntax),
+        HEX_TOKEN => folder.fold_hex_token(HexToken { syntax: node }).map(|it| it.syntax),
+        HEX_BYTE => folder.fold_hex_byte(HexByte { syntax: node }).map(|it| it.syntax),
+        HEX_ALTERNATIVE => {
+            folder.fold_hex_alternative(HexAlternative { syntax: node }).map(|it| it.syntax)
+        }
+        HEX_TOKEN_TAIL => {
+            folder.fold_hex_token_tail(HexTokenTail { syntax: node }).map(|it| it.syntax)
+        }
+        HEX_JUMP => folder.fold_hex_jump(HexJump { syntax: node }).map(|it| it.syntax),
+        HEX_PIPE => folder.fold_hex_pipe(HexPipe { syntax: node }).map(|it| it.syntax),
+        BASE_ALPHABET => {
+            folder.fold_base_alphabet(BaseAlphabet { syntax: node }).map(|it| it.syntax)
+        }
+        XOR_RANGE => folder.fold_xor_range(XorRange { syntax: node }).map(|it| it.syntax),
+        EXPRESSION_STMT => {
+            folder.fold_expression_stmt(ExpressionStmt { syntax: node }).map(|it| it.syntax)
+        }
+        BOOLEAN_EXPR => folder.fold_boolean_expr(BooleanExpr { syntax: node }).map(|it| it.syntax),
+        BOOLEAN_TERM => folder.fold_boolean_term(BooleanTerm { syntax: node }).map(|it| it.syntax),
+        VARIABLE_ANCHOR => {
+            folder.fold_variable_anchor(VariableAnchor { syntax: node }).map(|it| it.syntax)
+        }
+        BOOLEAN_TERM_EXPR => {
+            folder.fold_boolean_term_expr(BooleanTermExpr { syntax: node }).map(|it| it.syntax)
+        }
+        OF_EXPR => folder.fold_of_expr(OfExpr { syntax: node }).map(|it| it.syntax),
+        FOR_EXPR => folder.fold_for_expr(ForExpr { syntax: node }).map(|it| it.syntax),
+        PRIMARY_EXPR => folder.fold_primary_expr(PrimaryExpr { syntax: node }).map(|it| it.syntax),
+        INDEXING_EXPR => {
+            folder.fold_indexing_expr(IndexingExpr { syntax: node }).map(|it| it.syntax)
+        }
+        FUNCTION_CALL_EXPR => {
+            folder.fold_function_call_expr(FunctionCallExpr { syntax: node }).map(|it| it.syntax)
+        }
+        EXPR_BODY => folder.fold_expr_body(ExprBody { syntax: node }).map(|it| it.syntax),
+        VARIABLE_COUNT => {
+            folder.fold_variable_count(VariableCount { syntax: node }).map(|it| it.syntax)
+        }
+        VARIABLE_OFFSET => {
+            folder.fold_variable_offset(VariableOffset { syntax: node }).map(|it| it.syntax)
+        }
+        VARIABLE_LENGTH => {
+            folder.fold_variable_length(VariableLength { syntax: node }).map(|it| it.syntax)
+        }
+        IDENTIFIER_NODE => {
+            folder.fold_identifier_node(IdentifierNode { syntax: node }).map(|it| it.syntax)
+        }
+        FIELD_ACESS => folder.fold_field_acess(FieldAcess { syntax: node }).map(|it| it.syntax),
+        IN_RANGE => folder.fold_in_range(InRange { syntax: node }).map(|it| it.syntax),
+        EXPR_INDEX => folder.fold_expr_index(ExprIndex { syntax: node }).map(|it| it.syntax),
+        RANGE => folder.fold_range(Range { syntax: node }).map(|it| it.syntax),
+        EXPR_TUPLE => folder.fold_expr_tuple(ExprTuple { syntax: node }).map(|it| it.syntax),
+        QUANTIFIER => folder.fold_quantifier(Quantifier { syntax: node }).map(|it| it.syntax),
+        PATTERN_IDENT_TUPLE => {
+            folder.fold_pattern_ident_tuple(PatternIdentTuple { syntax: node }).map(|it| it.syntax)
+        }
+        BOOLEAN_EXPR_TUPLE => {
+            folder.fold_boolean_expr_tuple(BooleanExprTuple { syntax: node }).map(|it| it.syntax)
+        }
+        NESTED_EXPR => folder.fold_nested_expr(NestedExpr { syntax: node }).map(|it| it.syntax),
+        VARIABLE_WILDCARD => {
+            folder.fold_variable_wildcard(VariableWildcard { syntax: node }).map(|it| it.syntax)
+        }
+        _ => folder.fold_untyped(node),
+    }
+}
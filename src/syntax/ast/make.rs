@@ -0,0 +1,534 @@
+//! Constructors for typed AST nodes
+//!
+//! Every constructor renders the YARA source of the node and parses it,
+//! so the result is always a well-formed node with all tokens and trivia.
+//! Values are described by the owned HIR types, string contents are escaped
+//! and binary operands are wrapped in parentheses when the operator precedence
+//! requires it, so `binary(Mul, 1 + 2, 3)` gives `(1 + 2) * 3`.
+//!
+//! Nodes are created in their own detached trees. The constructors panic
+//! if the inputs do not form a valid node (for example a pattern name without `$`),
+//! except for `meta_stmt`, which returns `None` for values YARA cannot write.
+
+use std::fmt::Write;
+
+use crate::{
+    hir::{HexToken, MetaValue, PatternModifier, Regex, RuleModifier},
+    syntax::ast::{self, AstNode, BinaryOp, Cond, QuantifierKind, UnaryOp},
+    SourceFile,
+};
+
+/// `rule name : tags { meta: ... strings: ... condition: ... }`
+/// Sections without any statements are omitted
+pub fn rule(
+    name: &str,
+    tags: &[&str],
+    meta: &[ast::MetaStmt],
+    strings: &[ast::VariableStmt],
+    condition: &Cond,
+) -> ast::Rule {
+    let mut text = format!("rule {}", name);
+    if !tags.is_empty() {
+        write!(text, " : {}", tags.join(" ")).unwrap();
+    }
+    text.push_str(" {\n");
+    if !meta.is_empty() {
+        text.push_str("    meta:\n");
+        for stmt in meta {
+            writeln!(text, "        {}", stmt.syntax().text()).unwrap();
+        }
+    }
+    if !strings.is_empty() {
+        text.push_str("    strings:\n");
+        for stmt in strings {
+            writeln!(text, "        {}", stmt.syntax().text()).unwrap();
+        }
+    }
+    writeln!(text, "    condition:\n        {}\n}}", cond_text(condition)).unwrap();
+    ast_from_text(&text)
+}
+
+/// `key = value` in the `meta` section
+///
+/// Returns `None` for negative numbers and infinite or NaN floats,
+/// metadata values can only be non-negative literals.
+/// Floats are always written in decimal notation, `1e20` gives `100000000000000000000.0`.
+pub fn meta_stmt(key: &str, value: &MetaValue) -> Option<ast::MetaStmt> {
+    let value = match value {
+        MetaValue::Bool(value) => value.to_string(),
+        MetaValue::Int(value) if *value >= 0 => value.to_string(),
+        MetaValue::Float(value) if value.is_finite() && value.is_sign_positive() => {
+            let mut text = value.to_string();
+            if !text.contains('.') {
+                text.push_str(".0");
+            }
+            text
+        }
+        MetaValue::Int(_) | MetaValue::Float(_) => return None,
+        MetaValue::String(value) => string_lit(value),
+    };
+    Some(ast_from_text(&format!("rule x {{ meta: {} = {} condition: true }}", key, value)))
+}
+
+/// `$name = "value" mods`
+pub fn text_pattern(name: &str, value: &[u8], mods: &[PatternModifier]) -> ast::VariableStmt {
+    pattern(name, &string_lit(value), mods)
+}
+
+/// `$name = { tokens } mods`
+pub fn hex_pattern(name: &str, tokens: &[HexToken], mods: &[PatternModifier]) -> ast::VariableStmt {
+    pattern(name, &format!("{{ {} }}", hex_tokens(tokens)), mods)
+}
+
+/// `$name = /regex/flags mods`
+pub fn regex_pattern(name: &str, regex: &Regex, mods: &[PatternModifier]) -> ast::VariableStmt {
+    pattern(name, &regex_lit(regex), mods)
+}
+
+fn pattern(name: &str, value: &str, mods: &[PatternModifier]) -> ast::VariableStmt {
+    let mut text = format!("{} = {}", name, value);
+    for modifier in mods {
+        write!(text, " {}", pattern_mod(modifier)).unwrap();
+    }
+    ast_from_text(&format!("rule x {{ strings: {} condition: true }}", text))
+}
+
+/// `private` or `global`
+pub fn modifier(modifier: RuleModifier) -> ast::Modifier {
+    let text = match modifier {
+        RuleModifier::Private => "private",
+        RuleModifier::Global => "global",
+    };
+    ast_from_text(&format!("{} rule x {{ condition: true }}", text))
+}
+
+/// Rule tag
+pub fn tag(name: &str) -> ast::Tag {
+    ast_from_text(&format!("rule x : {} {{ condition: true }}", name))
+}
+
+/// Statement of the `condition` section
+pub fn expression_stmt(cond: &Cond) -> ast::ExpressionStmt {
+    ast_from_text(&format!("rule x {{ condition: {} }}", cond_text(cond)))
+}
+
+/// `true` or `false`
+pub fn bool_lit(value: bool) -> Cond {
+    cond_from_text(&value.to_string())
+}
+
+pub fn int_lit(value: i64) -> Cond {
+    cond_from_text(&value.to_string())
+}
+
+/// String literal with escaped contents
+pub fn string_lit_expr(value: &[u8]) -> Cond {
+    cond_from_text(&string_lit(value))
+}
+
+pub fn regex_expr(regex: &Regex) -> Cond {
+    cond_from_text(&regex_lit(regex))
+}
+
+pub fn filesize() -> Cond {
+    cond_from_text("filesize")
+}
+
+pub fn entrypoint() -> Cond {
+    cond_from_text("entrypoint")
+}
+
+/// Plain identifier, for example a module name
+pub fn ident(name: &str) -> Cond {
+    cond_from_text(name)
+}
+
+/// Pattern match, count, offset or length depending on the sigil of `pattern`
+/// (`$a`, `#a`, `@a` or `!a`)
+pub fn pattern_ref(pattern: &str) -> Cond {
+    cond_from_text(pattern)
+}
+
+/// `base.field`
+pub fn field_access(base: &Cond, field: &str) -> Cond {
+    cond_from_text(&format!(
+        "{}.{}",
+        operand(base, BinaryOp::ExprOp(ast::ExprOp::Dot), false),
+        field
+    ))
+}
+
+/// `callee(args)`
+pub fn call(callee: &Cond, args: &[Cond]) -> Cond {
+    let args: Vec<_> = args.iter().map(cond_text).collect();
+    cond_from_text(&format!("{}({})", cond_text(callee), args.join(", ")))
+}
+
+/// `base[index]`
+pub fn index(base: &Cond, index: &Cond) -> Cond {
+    cond_from_text(&format!("{}[{}]", cond_text(base), cond_text(index)))
+}
+
+/// `( inner )`
+pub fn paren(inner: &Cond) -> Cond {
+    cond_from_text(&format!("({})", cond_text(inner)))
+}
+
+/// `op operand`, the operand is parenthesized if it is not a single term
+pub fn unary(op: UnaryOp, operand: &Cond) -> Cond {
+    let operand = match operand {
+        Cond::Binary { .. } => format!("({})", cond_text(operand)),
+        _ => cond_text(operand),
+    };
+    let text = match op {
+        UnaryOp::Not | UnaryOp::Defined => format!("{} {}", op, operand),
+        UnaryOp::Neg | UnaryOp::BitNot => format!("{}{}", op, operand),
+    };
+    cond_from_text(&text)
+}
+
+/// `lhs op rhs`, operands are parenthesized if they bind weaker than `op`
+pub fn binary(op: BinaryOp, lhs: &Cond, rhs: &Cond) -> Cond {
+    let lhs = operand(lhs, op, false);
+    let rhs = operand(rhs, op, true);
+    cond_from_text(&format!("{} {} {}", lhs, op, rhs))
+}
+
+/// `quantifier of (patterns)`, an empty list of patterns gives `quantifier of them`
+/// Patterns can end with `*` to match all patterns with the prefix
+pub fn of_expr(quantifier: &QuantifierKind, patterns: &[&str]) -> Cond {
+    let set = match patterns {
+        [] => "them".to_string(),
+        _ => format!("({})", patterns.join(", ")),
+    };
+    cond_from_text(&format!("{} of {}", quantifier_text(quantifier), set))
+}
+
+/// `for quantifier of (patterns) : ( body )`, an empty list of patterns means `them`
+pub fn for_of_expr(quantifier: &QuantifierKind, patterns: &[&str], body: &Cond) -> Cond {
+    let set = match patterns {
+        [] => "them".to_string(),
+        _ => format!("({})", patterns.join(", ")),
+    };
+    let quantifier = quantifier_text(quantifier);
+    cond_from_text(&format!("for {} of {} : ( {} )", quantifier, set, cond_text(body)))
+}
+
+fn quantifier_text(quantifier: &QuantifierKind) -> String {
+    match quantifier {
+        QuantifierKind::All => "all".to_string(),
+        QuantifierKind::Any => "any".to_string(),
+        QuantifierKind::None => "none".to_string(),
+        QuantifierKind::Number(n) => operand(n, BinaryOp::ExprOp(ast::ExprOp::Dot), false),
+        QuantifierKind::Percent(n) => {
+            format!("{}%", operand(n, BinaryOp::ExprOp(ast::ExprOp::Dot), false))
+        }
+    }
+}
+
+/// Text of the operand of `op`, parenthesized if needed
+/// Operators are left associative, so the right operand also needs parentheses
+/// for operators of the same precedence
+fn operand(cond: &Cond, op: BinaryOp, is_rhs: bool) -> String {
+    let precedence = precedence(cond);
+    if precedence < op.precedence() || (is_rhs && precedence == op.precedence()) {
+        format!("({})", cond_text(cond))
+    } else {
+        cond_text(cond)
+    }
+}
+
+/// How strong the expression binds when used as an operand
+fn precedence(cond: &Cond) -> u8 {
+    match cond {
+        Cond::Binary { op, .. } => op.precedence(),
+        // `not` and `defined` take a whole comparison as an operand,
+        // so they bind weaker than comparison but stronger than `and`
+        Cond::Unary { op: UnaryOp::Not | UnaryOp::Defined, .. } => 5,
+        Cond::Of { .. } | Cond::For { .. } | Cond::PatternMatch { anchor: Some(_), .. } => 5,
+        Cond::Count { range: Some(_), .. } => 5,
+        _ => u8::MAX,
+    }
+}
+
+fn cond_text(cond: &Cond) -> String {
+    match cond {
+        // The node of a field access in `a.b.c` only covers the field
+        Cond::FieldAccess { base, field, .. } => format!("{}.{}", cond_text(base), field.text()),
+        _ => cond.syntax().text().to_string().trim().to_string(),
+    }
+}
+
+/// Escape the bytes and put them into double quotes
+fn string_lit(value: &[u8]) -> String {
+    let mut res = String::with_capacity(value.len() + 2);
+    res.push('"');
+    for &byte in value {
+        match byte {
+            b'"' => res.push_str("\\\""),
+            b'\\' => res.push_str("\\\\"),
+            b'\n' => res.push_str("\\n"),
+            b'\t' => res.push_str("\\t"),
+            b'\r' => res.push_str("\\r"),
+            b' '..=b'~' => res.push(byte as char),
+            _ => write!(res, "\\x{:02x}", byte).unwrap(),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn regex_lit(regex: &Regex) -> String {
+    let mut res = format!("/{}/", regex.pattern);
+    if regex.case_insensitive {
+        res.push('i');
+    }
+    if regex.dot_matches_all {
+        res.push('s');
+    }
+    res
+}
+
+fn hex_tokens(tokens: &[HexToken]) -> String {
+    let tokens: Vec<_> = tokens
+        .iter()
+        .map(|token| match token {
+            HexToken::Byte { value, mask, negated } => {
+                let nibble = |value: u8, mask: u8| match mask {
+                    0 => '?',
+                    _ => {
+                        char::from_digit(u32::from(value & mask), 16).unwrap().to_ascii_uppercase()
+                    }
+                };
+                let tilde = if *negated { "~" } else { "" };
+                let high = nibble(value >> 4, mask >> 4);
+                let low = nibble(value & 0xf, mask & 0xf);
+                format!("{}{}{}", tilde, high, low)
+            }
+            HexToken::Jump { from, to } if from == to && from.is_some() => {
+                format!("[{}]", from.unwrap())
+            }
+            HexToken::Jump { from, to } => {
+                let bound = |it: &Option<u64>| it.map(|it| it.to_string()).unwrap_or_default();
+                format!("[{}-{}]", bound(from), bound(to))
+            }
+            HexToken::Alternative(alternatives) => {
+                let alternatives: Vec<_> = alternatives.iter().map(|it| hex_tokens(it)).collect();
+                format!("( {} )", alternatives.join(" | "))
+            }
+        })
+        .collect();
+    tokens.join(" ")
+}
+
+fn pattern_mod(modifier: &PatternModifier) -> String {
+    match modifier {
+        PatternModifier::Ascii => "ascii".to_string(),
+        PatternModifier::Wide => "wide".to_string(),
+        PatternModifier::Nocase => "nocase".to_string(),
+        PatternModifier::Private => "private".to_string(),
+        PatternModifier::Fullword => "fullword".to_string(),
+        PatternModifier::Xor { from: 0, to: 255 } => "xor".to_string(),
+        PatternModifier::Xor { from, to } if from == to => format!("xor({})", from),
+        PatternModifier::Xor { from, to } => format!("xor({}-{})", from, to),
+        PatternModifier::Base64(None) => "base64".to_string(),
+        PatternModifier::Base64(Some(alphabet)) => format!("base64({})", string_lit(alphabet)),
+        PatternModifier::Base64Wide(None) => "base64wide".to_string(),
+        PatternModifier::Base64Wide(Some(alphabet)) => {
+            format!("base64wide({})", string_lit(alphabet))
+        }
+    }
+}
+
+/// Standalone tokens that can be inserted into mutable trees
+pub mod tokens {
+    use rowan_test::{GreenNode, GreenToken, Language, NodeOrToken};
+
+    use super::ast_from_text;
+    use crate::{
+        syntax::ast::{self, AstNode},
+        SyntaxKind, SyntaxNode, SyntaxToken, YARALanguage,
+    };
+
+    /// Whitespace token with the given text, for example `"\n    "`
+    pub fn whitespace(text: &str) -> SyntaxToken {
+        assert!(
+            text.trim().is_empty(),
+            "Whitespace contains non-whitespace characters: {:?}",
+            text
+        );
+        let token = GreenToken::new(YARALanguage::kind_to_raw(SyntaxKind::WHITESPACE), text);
+        let root = GreenNode::new(
+            YARALanguage::kind_to_raw(SyntaxKind::SOURCE_FILE),
+            [NodeOrToken::Token(token)],
+        );
+        SyntaxNode::new_root(root).clone_for_update().first_token().unwrap()
+    }
+
+    /// Pattern identifier, for example `$a`
+    pub fn variable(name: &str) -> SyntaxToken {
+        let text = format!("rule x {{ strings: {} = \"\" condition: true }}", name);
+        let stmt: ast::VariableStmt = ast_from_text(&text);
+        let token = stmt.clone_for_update().variable_token().unwrap();
+        token.detach();
+        token
+    }
+
+    /// `:` between the rule name and tags
+    pub fn colon() -> SyntaxToken {
+        let rule: ast::Rule = ast_from_text("rule x : t { condition: true }");
+        let colon = rule.clone_for_update().colon_token().unwrap();
+        colon.detach();
+        colon
+    }
+}
+
+/// Parse the condition and return its expression
+fn cond_from_text(text: &str) -> Cond {
+    let condition: ast::Condition = ast_from_text(&format!("rule x {{ condition: {} }}", text));
+    condition
+        .expression_stmt()
+        .and_then(|it| Cond::cast(it.syntax().clone_subtree()))
+        .filter(|it| !matches!(it, Cond::Error { .. }))
+        .unwrap_or_else(|| panic!("Failed to make condition from `{}`", text))
+}
+
+/// Parse the text and return the first node of type `N`
+fn ast_from_text<N: AstNode>(text: &str) -> N {
+    let parse = SourceFile::parse(text);
+    if !parse.errors().is_empty() {
+        panic!("Failed to make ast node from `{}`: {:?}", text, parse.errors());
+    }
+    match parse.tree().syntax().descendants().find_map(N::cast) {
+        Some(node) => N::cast(node.syntax().clone_subtree()).unwrap(),
+        None => panic!("Failed to make ast node `{}` from `{}`", std::any::type_name::<N>(), text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::{self, ExprKind, LogicOp, PatternKind};
+
+    fn add(lhs: &Cond, rhs: &Cond) -> Cond {
+        binary(BinaryOp::ExprOp(ast::ExprOp::Add), lhs, rhs)
+    }
+
+    #[test]
+    fn make_rule() {
+        let condition = binary(
+            BinaryOp::LogicOp(LogicOp::And),
+            &of_expr(&QuantifierKind::Any, &["$a", "$b*"]),
+            &binary(BinaryOp::BoolTermExprOp(ast::BoolTermExprOp::Lt), &filesize(), &int_lit(1024)),
+        );
+        let rule = rule(
+            "generated",
+            &["sandbox"],
+            &[meta_stmt("source", &MetaValue::String(b"report \"42\"".to_vec())).unwrap()],
+            &[
+                text_pattern("$a", b"C:\\evil\x00.exe", &[PatternModifier::Wide]),
+                hex_pattern(
+                    "$b1",
+                    &[
+                        HexToken::Byte { value: 0x4d, mask: 0xff, negated: false },
+                        HexToken::Byte { value: 0x5a, mask: 0x0f, negated: true },
+                        HexToken::Jump { from: Some(2), to: Some(4) },
+                        HexToken::Alternative(vec![
+                            vec![HexToken::Byte { value: 0x00, mask: 0xff, negated: false }],
+                            vec![
+                                HexToken::Byte { value: 0x01, mask: 0xff, negated: false },
+                                HexToken::Jump { from: Some(1), to: Some(1) },
+                                HexToken::Byte { value: 0x02, mask: 0xff, negated: false },
+                            ],
+                        ]),
+                    ],
+                    &[],
+                ),
+            ],
+            &condition,
+        );
+        assert_eq!(
+            rule.syntax().to_string(),
+            r#"rule generated : sandbox {
+    meta:
+        source = "report \"42\""
+    strings:
+        $a = "C:\\evil\x00.exe" wide
+        $b1 = { 4D ~?A [2-4] ( 00 | 01 [1] 02 ) }
+    condition:
+        any of ($a, $b*) and filesize < 1024
+}"#
+        );
+
+        let (file, errors) = hir::lower(&SourceFile::parse(&rule.syntax().to_string()).tree());
+        assert!(errors.is_empty(), "{:?}", errors);
+        let pattern = &file.rules[0].patterns[0];
+        assert_eq!(pattern.kind, PatternKind::Text(b"C:\\evil\x00.exe".to_vec()));
+    }
+
+    #[test]
+    fn meta_values() {
+        let text = |value: MetaValue| meta_stmt("a", &value).map(|it| it.syntax().to_string());
+        assert_eq!(text(MetaValue::Int(0)).as_deref(), Some("a = 0"));
+        assert_eq!(text(MetaValue::Float(0.5)).as_deref(), Some("a = 0.5"));
+        assert_eq!(text(MetaValue::Float(2.0)).as_deref(), Some("a = 2.0"));
+        assert_eq!(text(MetaValue::Float(1e20)).as_deref(), Some("a = 100000000000000000000.0"));
+        assert_eq!(text(MetaValue::Float(1e-7)).as_deref(), Some("a = 0.0000001"));
+        assert_eq!(text(MetaValue::Int(-1)), None);
+        assert_eq!(text(MetaValue::Float(-0.5)), None);
+        assert_eq!(text(MetaValue::Float(f64::NAN)), None);
+        assert_eq!(text(MetaValue::Float(f64::INFINITY)), None);
+    }
+
+    #[test]
+    fn parentheses() {
+        let mul = BinaryOp::ExprOp(ast::ExprOp::Mul);
+        let sub = BinaryOp::ExprOp(ast::ExprOp::Sub);
+        let (one, two, three) = (int_lit(1), int_lit(2), int_lit(3));
+
+        assert_eq!(cond_text(&binary(mul, &add(&one, &two), &three)), "(1 + 2) * 3");
+        assert_eq!(cond_text(&add(&binary(mul, &one, &two), &three)), "1 * 2 + 3");
+        assert_eq!(cond_text(&binary(sub, &one, &binary(sub, &two, &three))), "1 - (2 - 3)");
+        assert_eq!(cond_text(&binary(sub, &binary(sub, &one, &two), &three)), "1 - 2 - 3");
+
+        let or = BinaryOp::LogicOp(LogicOp::Or);
+        let and = BinaryOp::LogicOp(LogicOp::And);
+        let (a, b) = (pattern_ref("$a"), pattern_ref("$b"));
+        let not_or = unary(UnaryOp::Not, &binary(or, &a, &b));
+        assert_eq!(cond_text(&not_or), "not ($a or $b)");
+        assert_eq!(cond_text(&binary(and, &not_or, &a)), "not ($a or $b) and $a");
+        assert_eq!(cond_text(&binary(and, &binary(or, &a, &b), &a)), "($a or $b) and $a");
+
+        // The lowered tree has the same structure as the one that was built
+        let cond = binary(mul, &add(&one, &two), &unary(UnaryOp::Neg, &three));
+        let text = format!("rule x {{ condition: {} }}", cond_text(&cond));
+        let (file, _) = hir::lower(&SourceFile::parse(&text).tree());
+        let condition = file.rules[0].condition.as_ref().unwrap();
+        let ExprKind::Binary { op, lhs, .. } = &condition.kind else { panic!("{:?}", condition) };
+        assert_eq!(*op, mul);
+        assert!(matches!(
+            lhs.kind,
+            ExprKind::Binary { op: BinaryOp::ExprOp(ast::ExprOp::Add), .. }
+        ));
+    }
+
+    #[test]
+    fn expressions() {
+        let pe = field_access(&ident("pe"), "sections");
+        let section = field_access(&index(&pe, &int_lit(0)), "name");
+        assert_eq!(cond_text(&section), "pe.sections[0].name");
+        assert!(matches!(section, Cond::FieldAccess { .. }));
+
+        let call = call(&field_access(&ident("math"), "entropy"), &[int_lit(0), filesize()]);
+        assert_eq!(cond_text(&call), "math.entropy(0, filesize)");
+
+        let percent = QuantifierKind::Percent(Box::new(int_lit(50)));
+        assert_eq!(cond_text(&of_expr(&percent, &[])), "50% of them");
+        let body = pattern_ref("$");
+        assert_eq!(
+            cond_text(&for_of_expr(&QuantifierKind::All, &["$a"], &body)),
+            "for all of ($a) : ( $ )"
+        );
+        assert_eq!(cond_text(&string_lit_expr(b"\"\t\xff")), r#""\"\t\xff""#);
+    }
+}
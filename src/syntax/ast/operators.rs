@@ -2,7 +2,10 @@
 
 use std::fmt;
 
+use crate::{SyntaxKind, T};
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOp {
     LogicOp(LogicOp),
     ExprOp(ExprOp),
@@ -10,12 +13,27 @@ pub enum BinaryOp {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnaryOp {
+    /// `not`
+    Not,
+    /// `defined`
+    Defined,
+    /// `-`
+    Neg,
+    /// `~`
+    BitNot,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LogicOp {
     And,
     Or,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExprOp {
     Add,
     Sub,
@@ -31,6 +49,7 @@ pub enum ExprOp {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BoolTermExprOp {
     Eq,
     Ne,
@@ -48,6 +67,76 @@ pub enum BoolTermExprOp {
     Matches,
 }
 
+impl BinaryOp {
+    /// Binary operator for the given operator token
+    pub fn from_kind(kind: SyntaxKind) -> Option<BinaryOp> {
+        let op = match kind {
+            T![and] => BinaryOp::LogicOp(LogicOp::And),
+            T![or] => BinaryOp::LogicOp(LogicOp::Or),
+            T![+] => BinaryOp::ExprOp(ExprOp::Add),
+            T![-] => BinaryOp::ExprOp(ExprOp::Sub),
+            T![*] => BinaryOp::ExprOp(ExprOp::Mul),
+            T![backslash] => BinaryOp::ExprOp(ExprOp::Div),
+            T![%] => BinaryOp::ExprOp(ExprOp::Mod),
+            T![&] => BinaryOp::ExprOp(ExprOp::BitAnd),
+            T![|] => BinaryOp::ExprOp(ExprOp::BitOr),
+            T![^] => BinaryOp::ExprOp(ExprOp::BitXor),
+            T![<<] => BinaryOp::ExprOp(ExprOp::Shl),
+            T![>>] => BinaryOp::ExprOp(ExprOp::Shr),
+            T![.] => BinaryOp::ExprOp(ExprOp::Dot),
+            T![==] => BinaryOp::BoolTermExprOp(BoolTermExprOp::Eq),
+            T![!=] => BinaryOp::BoolTermExprOp(BoolTermExprOp::Ne),
+            T![>] => BinaryOp::BoolTermExprOp(BoolTermExprOp::Gt),
+            T![<] => BinaryOp::BoolTermExprOp(BoolTermExprOp::Lt),
+            T![>=] => BinaryOp::BoolTermExprOp(BoolTermExprOp::Ge),
+            T![<=] => BinaryOp::BoolTermExprOp(BoolTermExprOp::Le),
+            T![contains] => BinaryOp::BoolTermExprOp(BoolTermExprOp::Contains),
+            T![icontains] => BinaryOp::BoolTermExprOp(BoolTermExprOp::IContains),
+            T![startswith] => BinaryOp::BoolTermExprOp(BoolTermExprOp::StartsWith),
+            T![istartswith] => BinaryOp::BoolTermExprOp(BoolTermExprOp::IStartsWith),
+            T![endswith] => BinaryOp::BoolTermExprOp(BoolTermExprOp::EndsWith),
+            T![iendswith] => BinaryOp::BoolTermExprOp(BoolTermExprOp::IEndsWith),
+            T![iequals] => BinaryOp::BoolTermExprOp(BoolTermExprOp::IEquals),
+            T![matches] => BinaryOp::BoolTermExprOp(BoolTermExprOp::Matches),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Precedence of the operator, higher binds tighter
+    /// It is the same as the binding power used by the parser,
+    /// all binary operators are left associative
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOp::LogicOp(LogicOp::Or) => 2,
+            BinaryOp::LogicOp(LogicOp::And) => 4,
+            BinaryOp::BoolTermExprOp(
+                BoolTermExprOp::Gt | BoolTermExprOp::Lt | BoolTermExprOp::Ge | BoolTermExprOp::Le,
+            ) => 8,
+            BinaryOp::BoolTermExprOp(_) => 6,
+            BinaryOp::ExprOp(ExprOp::BitOr) => 10,
+            BinaryOp::ExprOp(ExprOp::BitXor) => 12,
+            BinaryOp::ExprOp(ExprOp::BitAnd) => 14,
+            BinaryOp::ExprOp(ExprOp::Shl | ExprOp::Shr) => 16,
+            BinaryOp::ExprOp(ExprOp::Add | ExprOp::Sub) => 18,
+            BinaryOp::ExprOp(ExprOp::Mul | ExprOp::Div | ExprOp::Mod) => 20,
+            BinaryOp::ExprOp(ExprOp::Dot) => 22,
+        }
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let res = match self {
+            UnaryOp::Not => "not",
+            UnaryOp::Defined => "defined",
+            UnaryOp::Neg => "-",
+            UnaryOp::BitNot => "~",
+        };
+        f.write_str(res)
+    }
+}
+
 impl fmt::Display for LogicOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let res = match self {
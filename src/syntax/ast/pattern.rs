@@ -0,0 +1,155 @@
+//! Typed view over pattern declarations
+//!
+//! `PatternKind` tells which kind of pattern is declared without probing
+//! each accessor of `ast::Pattern`, and `PatternModifiers` decodes the flat list
+//! of `PatternMod` nodes into a set of modifiers with YARA's defaults applied.
+//! Every modifier keeps the range of its node, so it is possible to report
+//! errors for them (for example for incompatible modifiers).
+
+use std::ops::RangeInclusive;
+
+use text_size::TextRange;
+
+use crate::{
+    hir::{self, PatternModifier},
+    syntax::ast::{self, AstNode},
+};
+
+/// Value of a pattern declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+    /// Text string with decoded escape sequences
+    Text(Vec<u8>),
+    Hex(ast::HexPattern),
+    Regex(ast::RegexPattern),
+}
+
+/// Pattern modifier with its value and the range of its node
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModifierValue<T = ()> {
+    pub value: T,
+    /// `None` for modifiers that are not written but implied by YARA's defaults
+    pub range: Option<TextRange>,
+}
+
+/// Alphabet of the `base64` and `base64wide` modifiers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Alphabet {
+    Default,
+    /// Custom alphabet of 64 bytes
+    Custom(Vec<u8>),
+}
+
+/// Decoded set of pattern modifiers
+///
+/// `ascii` is implied if there is no `wide` modifier and `xor` without a range
+/// means all keys (`0..=255`). If a modifier is written more than once, the first one is used.
+/// Modifiers with invalid values (for example `xor(300)`) are left out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatternModifiers {
+    pub ascii: Option<ModifierValue>,
+    pub wide: Option<ModifierValue>,
+    pub nocase: Option<ModifierValue>,
+    pub fullword: Option<ModifierValue>,
+    pub private: Option<ModifierValue>,
+    pub xor: Option<ModifierValue<RangeInclusive<u8>>>,
+    pub base64: Option<ModifierValue<Alphabet>>,
+    pub base64wide: Option<ModifierValue<Alphabet>>,
+}
+
+impl PatternModifiers {
+    pub(super) fn lower(pattern: &ast::Pattern) -> PatternModifiers {
+        fn set<T>(field: &mut Option<ModifierValue<T>>, value: T, range: TextRange) {
+            field.get_or_insert(ModifierValue { value, range: Some(range) });
+        }
+        fn alphabet(alphabet: Option<Vec<u8>>) -> Alphabet {
+            alphabet.map_or(Alphabet::Default, Alphabet::Custom)
+        }
+
+        let mut res = PatternModifiers::default();
+        for node in pattern.pattern_mods() {
+            let range = node.syntax().text_range();
+            match hir::lower_pattern_modifier(&node) {
+                Some(PatternModifier::Ascii) => set(&mut res.ascii, (), range),
+                Some(PatternModifier::Wide) => set(&mut res.wide, (), range),
+                Some(PatternModifier::Nocase) => set(&mut res.nocase, (), range),
+                Some(PatternModifier::Fullword) => set(&mut res.fullword, (), range),
+                Some(PatternModifier::Private) => set(&mut res.private, (), range),
+                Some(PatternModifier::Xor { from, to }) => set(&mut res.xor, from..=to, range),
+                Some(PatternModifier::Base64(it)) => set(&mut res.base64, alphabet(it), range),
+                Some(PatternModifier::Base64Wide(it)) => {
+                    set(&mut res.base64wide, alphabet(it), range)
+                }
+                None => (),
+            }
+        }
+        if res.wide.is_none() {
+            res.ascii.get_or_insert(ModifierValue { value: (), range: None });
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceFile;
+
+    fn patterns(text: &str) -> Vec<ast::Pattern> {
+        let text = format!("rule a {{ strings: {} condition: true }}", text);
+        SourceFile::parse(&text)
+            .tree()
+            .syntax()
+            .descendants()
+            .filter_map(ast::Pattern::cast)
+            .collect()
+    }
+
+    #[test]
+    fn pattern_kind() {
+        let patterns = patterns(r#"$a = "a\x00\"" $b = { 00 } $c = /ab/i $d = "\q""#);
+        let kinds: Vec<_> = patterns.iter().map(|it| it.kind()).collect();
+        assert_eq!(kinds[0], Some(PatternKind::Text(b"a\x00\"".to_vec())));
+        assert!(matches!(kinds[1], Some(PatternKind::Hex(_))));
+        assert!(
+            matches!(&kinds[2], Some(PatternKind::Regex(regex)) if regex.syntax().text() == "/ab/i")
+        );
+        assert_eq!(kinds[3], None);
+    }
+
+    #[test]
+    fn modifiers() {
+        let patterns = patterns(
+            r#"
+            $a = "a"
+            $b = "b" wide nocase xor(1-3) xor
+            $c = "c" wide ascii private base64
+            $d = "d" fullword xor(300) base64wide("!@#$%^&*(){}[].,|ABCDEFGHIJ\x09LMNOPQRSTUVWXYZabcdefghijklmnopqrstu")
+            "#,
+        );
+        let modifiers: Vec<_> = patterns.iter().map(|it| it.modifiers()).collect();
+
+        let implied = Some(ModifierValue { value: (), range: None });
+        assert_eq!(modifiers[0], PatternModifiers { ascii: implied.clone(), ..Default::default() });
+
+        assert!(modifiers[1].ascii.is_none());
+        assert!(modifiers[1].wide.is_some() && modifiers[1].nocase.is_some());
+        let xor = modifiers[1].xor.as_ref().unwrap();
+        assert_eq!(xor.value, 1..=3);
+        let text = patterns[1].syntax().to_string();
+        let start = patterns[1].syntax().text_range().start();
+        assert_eq!(&text[xor.range.unwrap() - start], "xor(1-3)");
+
+        assert!(modifiers[2].ascii.as_ref().unwrap().range.is_some());
+        assert!(modifiers[2].private.is_some());
+        assert_eq!(modifiers[2].base64.as_ref().unwrap().value, Alphabet::Default);
+
+        assert_eq!(modifiers[3].ascii, implied);
+        assert!(modifiers[3].fullword.is_some());
+        assert_eq!(modifiers[3].xor, None);
+        assert!(matches!(
+            modifiers[3].base64wide.as_ref().unwrap().value,
+            Alphabet::Custom(ref it) if it.len() == 64
+        ));
+    }
+}
@@ -1,10 +1,11 @@
 //! Implementation of these traits is in `generated/` folder
-//! So far the only implemented trait is `HasComments` which is used
-//! to iterate over comments in the syntax tree
-//! This can be easily extended to support other traits
+//! The traits are assigned to nodes by `sourcegen_ast` based on their fields
+//! in `yara.ungram` (for example the `name:` label), so code can work with
+//! all nodes that have a name, modifiers or tags without matching each node type
 
-use crate::syntax::ast::{self, AstNode};
+use crate::syntax::ast::{self, support, AstChildren, AstNode};
 use crate::syntax::syntax_node::SyntaxElementChildren;
+use crate::{SyntaxKind, SyntaxToken, T};
 
 use super::AstToken;
 
@@ -30,3 +31,118 @@ impl Iterator for CommentIter {
         self.iter.by_ref().find_map(|el| el.into_token().and_then(ast::Comment::cast))
     }
 }
+
+pub trait HasName: AstNode {
+    /// Identifier of the rule, metadata, pattern or module
+    fn name(&self) -> Option<Name> {
+        self.syntax().children_with_tokens().filter_map(|it| it.into_token()).find_map(Name::cast)
+    }
+}
+
+pub trait HasModifiers: AstNode {
+    fn is_private(&self) -> bool {
+        has_modifier(self, T![private])
+    }
+
+    /// Patterns are never global
+    fn is_global(&self) -> bool {
+        has_modifier(self, T![global])
+    }
+}
+
+fn has_modifier<N: HasModifiers + ?Sized>(node: &N, kind: SyntaxKind) -> bool {
+    node.syntax()
+        .children()
+        .filter(|it| matches!(it.kind(), SyntaxKind::MODIFIER | SyntaxKind::PATTERN_MOD))
+        .any(|it| support::token(&it, kind).is_some())
+}
+
+pub trait HasTags: AstNode {
+    fn tags(&self) -> AstChildren<ast::Tag> {
+        support::children(self.syntax())
+    }
+
+    fn tag_names(&self) -> Vec<String> {
+        HasTags::tags(self)
+            .filter_map(|it| it.identifier_token())
+            .map(|it| it.text().to_string())
+            .collect()
+    }
+}
+
+/// Identifier token of a node with `HasName`, pattern names include the `$`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Name {
+    pub(crate) syntax: SyntaxToken,
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.syntax, f)
+    }
+}
+
+impl AstToken for Name {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        matches!(kind, SyntaxKind::IDENTIFIER | SyntaxKind::VARIABLE)
+    }
+
+    fn cast(syntax: SyntaxToken) -> Option<Self> {
+        Self::can_cast(syntax.kind()).then_some(Self { syntax })
+    }
+
+    fn syntax(&self) -> &SyntaxToken {
+        &self.syntax
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        syntax::ast::{AnyHasName, AstNode, AstToken, HasModifiers, HasName, HasTags},
+        SourceFile,
+    };
+
+    #[test]
+    fn shared_traits() {
+        let file = SourceFile::parse(
+            r#"
+private global rule a : foo bar {
+    meta:
+        author = "me"
+    strings:
+        $a = "a" private wide
+        $b = "b"
+    condition:
+        pe.is_dll() and $a
+}
+rule b { condition: a }
+"#,
+        )
+        .tree();
+        let names: Vec<_> = file
+            .syntax()
+            .descendants()
+            .filter_map(AnyHasName::cast)
+            .filter_map(|it| it.name())
+            .map(|it| it.text().to_string())
+            .collect();
+        assert_eq!(names, ["a", "author", "$a", "$b", "pe", "is_dll", "b", "a"]);
+
+        let rules: Vec<_> = file.rules().collect();
+        assert!(rules[0].is_private() && rules[0].is_global());
+        assert!(!rules[1].is_private() && !rules[1].is_global());
+        assert_eq!(rules[0].tag_names(), ["foo", "bar"]);
+        assert!(rules[1].tag_names().is_empty());
+
+        let patterns: Vec<_> = rules[0]
+            .body()
+            .and_then(|it| it.strings())
+            .into_iter()
+            .flat_map(|it| it.variable_stmts())
+            .filter_map(|it| it.pattern())
+            .collect();
+        assert!(patterns[0].is_private() && !patterns[0].is_global());
+        assert!(!patterns[1].is_private());
+    }
+}
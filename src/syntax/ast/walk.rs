@@ -0,0 +1,142 @@
+//! Default traversals used by the generated `Visitor` and `Fold` traits
+
+use rowan_test::{GreenNode, Language, NodeOrToken};
+
+use crate::{
+    syntax::{
+        ast::generated::visit::{fold_node, visit_node, Fold, Visitor},
+        syntax_node::YARALanguage,
+    },
+    SyntaxNode,
+};
+
+/// Visit all children of the node, both nodes and tokens, in source order
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &SyntaxNode) {
+    for child in node.children_with_tokens() {
+        match child {
+            NodeOrToken::Node(node) => visit_node(visitor, &node),
+            NodeOrToken::Token(token) => visitor.visit_token(&token),
+        }
+    }
+}
+
+/// Fold all children of the node
+/// The node is returned unchanged if all children are kept as they are,
+/// otherwise a new detached node of the same kind is built from the folded children.
+pub fn fold_children<F: Fold + ?Sized>(folder: &mut F, node: SyntaxNode) -> SyntaxNode {
+    let mut changed = false;
+    let mut children = Vec::new();
+    for child in node.children_with_tokens() {
+        match child {
+            NodeOrToken::Node(child) => match fold_node(folder, child.clone()) {
+                Some(new) => {
+                    changed |= new != child;
+                    children.push(NodeOrToken::Node(new.green().into_owned()));
+                }
+                None => changed = true,
+            },
+            NodeOrToken::Token(child) => match folder.fold_token(child.clone()) {
+                Some(new) => {
+                    changed |= new != child;
+                    children.push(NodeOrToken::Token(new.green().to_owned()));
+                }
+                None => changed = true,
+            },
+        }
+    }
+    if !changed {
+        return node;
+    }
+    SyntaxNode::new_root(GreenNode::new(YARALanguage::kind_to_raw(node.kind()), children))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        syntax::ast::{self, AstNode, Fold, Visitor},
+        SourceFile, SyntaxKind, SyntaxToken,
+    };
+
+    const TEXT: &str = r#"
+rule a {
+    strings:
+        $a = "foo"
+        $b = "bar"
+    condition:
+        $a and #b > 2 or (pe.is_dll() and $a at 0)
+}
+"#;
+
+    #[derive(Default)]
+    struct PatternRefs {
+        refs: Vec<String>,
+        in_conditions: bool,
+    }
+
+    impl Visitor for PatternRefs {
+        fn visit_condition(&mut self, node: &ast::Condition) {
+            self.in_conditions = true;
+            crate::walk_node(self, node.syntax());
+            self.in_conditions = false;
+        }
+
+        fn visit_token(&mut self, token: &SyntaxToken) {
+            let is_ref = matches!(token.kind(), SyntaxKind::VARIABLE | SyntaxKind::VARIABLE_COUNT);
+            if self.in_conditions && is_ref {
+                self.refs.push(token.text().to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn visitor() {
+        let file = SourceFile::parse(TEXT).tree();
+        let mut visitor = PatternRefs::default();
+        crate::visit_node(&mut visitor, file.syntax());
+        assert_eq!(visitor.refs, ["$a", "#b", "$a"]);
+    }
+
+    /// Rename `$a` to `$renamed` and remove the `$b` declaration
+    struct Rename {
+        renamed: SyntaxToken,
+    }
+
+    impl Fold for Rename {
+        fn fold_variable_stmt(&mut self, node: ast::VariableStmt) -> Option<ast::VariableStmt> {
+            if node.variable_token()?.text() == "$b" {
+                return None;
+            }
+            ast::VariableStmt::cast(crate::fold_children(self, node.syntax().clone()))
+        }
+
+        fn fold_token(&mut self, token: SyntaxToken) -> Option<SyntaxToken> {
+            if token.kind() == SyntaxKind::VARIABLE && token.text() == "$a" {
+                return Some(self.renamed.clone());
+            }
+            Some(token)
+        }
+    }
+
+    #[test]
+    fn fold() {
+        let file = SourceFile::parse(TEXT).tree();
+        let renamed = SourceFile::parse("rule x { condition: $renamed }")
+            .syntax_node()
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == SyntaxKind::VARIABLE)
+            .unwrap();
+
+        let new = crate::fold_node(&mut Rename { renamed }, file.syntax().clone()).unwrap();
+        // Only the statement is removed, the surrounding whitespace is kept
+        let expected = TEXT.replace("$b = \"bar\"", "").replace("$a", "$renamed");
+        assert_eq!(new.to_string(), expected);
+        assert!(ast::SourceFile::cast(new).is_some());
+
+        // Nothing changes, so the same tree is returned
+        struct Identity;
+        impl Fold for Identity {}
+        let same = crate::fold_node(&mut Identity, file.syntax().clone()).unwrap();
+        assert_eq!(&same, file.syntax());
+    }
+}
@@ -0,0 +1,203 @@
+//! Public, tree-independent view of the parser output
+//!
+//! `SourceFile::parse` feeds everything the parser produces into a rowan tree builder.
+//! This module exposes the same stream to users, so they can build their own
+//! representation (or nothing at all) without going through `SyntaxNode`.
+//!
+//! The stream is exactly the one used to build the rowan tree, including trivia
+//! and their attachment to nodes. Lexer errors are reported after all parser events,
+//! in the same order as in `Parse::errors()`.
+
+use text_size::{TextRange, TextSize};
+
+use crate::{
+    lexer::tokenize,
+    parser::{self, SyntaxKind},
+    syntax::{
+        syntax_error::SyntaxError, text_token_source::TextTokenSource, text_tree_sink::TextTreeSink,
+    },
+};
+
+/// Receiver of the parser output
+///
+/// Implement this trait to consume the result of parsing without building
+/// a rowan syntax tree. Calls to `start_node` and `finish_node` are always balanced.
+pub trait EventSink {
+    /// Start a new node, all following tokens and nodes are its children
+    /// until the matching `finish_node`
+    fn start_node(&mut self, kind: SyntaxKind);
+
+    /// Add a token (including whitespaces and comments) to the current node
+    fn token(&mut self, kind: SyntaxKind, range: TextRange, text: &str);
+
+    /// Finish the current node and return control to the parent
+    fn finish_node(&mut self);
+
+    /// Report a syntax error
+    fn error(&mut self, error: SyntaxError);
+}
+
+/// Parse the given text and feed the resulting events into `sink`
+pub fn parse_with(text: &str, sink: &mut dyn EventSink) {
+    let (tokens, lexer_errors) = tokenize(text);
+    let mut token_source = TextTokenSource::new(text, &tokens);
+    let mut tree_sink = TextTreeSink::new(text, &tokens, sink);
+
+    parser::parse(&mut token_source, &mut tree_sink);
+    let sink = tree_sink.finish();
+    for error in lexer_errors {
+        sink.error(error);
+    }
+}
+
+/// Single event of the parser output
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseEvent {
+    /// Start of a node, `range` covers the whole node
+    Start { kind: SyntaxKind, range: TextRange },
+    /// Token attached to the current node
+    Token { kind: SyntaxKind, range: TextRange },
+    /// End of the most recently started node
+    Finish,
+    /// Syntax error
+    Error(SyntaxError),
+}
+
+/// An iterator over the events produced by parsing a text
+#[derive(Debug, Clone)]
+pub struct ParseEvents {
+    inner: std::vec::IntoIter<ParseEvent>,
+}
+
+impl Iterator for ParseEvents {
+    type Item = ParseEvent;
+
+    fn next(&mut self) -> Option<ParseEvent> {
+        self.inner.next()
+    }
+}
+
+/// Parse the given text into a stream of `ParseEvent`s
+pub fn parse_events(text: &str) -> ParseEvents {
+    let mut collector = EventCollector::default();
+    parse_with(text, &mut collector);
+    ParseEvents { inner: collector.events.into_iter() }
+}
+
+/// Parse the given text and return only the syntax errors
+///
+/// This is the fast path for validation, no syntax tree is built.
+pub fn validate(text: &str) -> Vec<SyntaxError> {
+    let mut errors = ErrorCollector::default();
+    parse_with(text, &mut errors);
+    errors.0
+}
+
+/// Collects all events, ranges of `Start` events are filled in
+/// once the node is finished
+#[derive(Default)]
+struct EventCollector {
+    events: Vec<ParseEvent>,
+    open_nodes: Vec<usize>,
+    text_pos: TextSize,
+}
+
+impl EventSink for EventCollector {
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.open_nodes.push(self.events.len());
+        self.events.push(ParseEvent::Start { kind, range: TextRange::empty(self.text_pos) });
+    }
+
+    fn token(&mut self, kind: SyntaxKind, range: TextRange, _text: &str) {
+        self.text_pos = range.end();
+        self.events.push(ParseEvent::Token { kind, range });
+    }
+
+    fn finish_node(&mut self) {
+        let idx = self.open_nodes.pop().expect("unbalanced finish_node");
+        match &mut self.events[idx] {
+            ParseEvent::Start { range, .. } => {
+                *range = TextRange::new(range.start(), self.text_pos)
+            }
+            _ => unreachable!(),
+        }
+        self.events.push(ParseEvent::Finish);
+    }
+
+    fn error(&mut self, error: SyntaxError) {
+        self.events.push(ParseEvent::Error(error));
+    }
+}
+
+/// Ignores everything but errors
+#[derive(Default)]
+struct ErrorCollector(Vec<SyntaxError>);
+
+impl EventSink for ErrorCollector {
+    fn start_node(&mut self, _kind: SyntaxKind) {}
+
+    fn token(&mut self, _kind: SyntaxKind, _range: TextRange, _text: &str) {}
+
+    fn finish_node(&mut self) {}
+
+    fn error(&mut self, error: SyntaxError) {
+        self.0.push(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AstNode, NodeOrToken, SourceFile, WalkEvent};
+
+    const SOURCE: &str = r#"
+        import "pe"
+
+        // Rule comment
+        private rule test : tag {
+            strings:
+                $a = "foo" ascii // trailing
+                $b = { AB ?? [2-4] CD }
+            condition:
+                $a and #b > 2 §
+        }
+    "#;
+
+    #[test]
+    fn events_match_syntax_tree() {
+        let parse = SourceFile::parse(SOURCE);
+        let expected: Vec<_> = parse
+            .tree()
+            .syntax()
+            .preorder_with_tokens()
+            .map(|event| match event {
+                WalkEvent::Enter(NodeOrToken::Node(node)) => {
+                    ParseEvent::Start { kind: node.kind(), range: node.text_range() }
+                }
+                WalkEvent::Enter(NodeOrToken::Token(token)) => {
+                    ParseEvent::Token { kind: token.kind(), range: token.text_range() }
+                }
+                WalkEvent::Leave(_) => ParseEvent::Finish,
+            })
+            .filter(|event| !matches!(event, ParseEvent::Finish))
+            .collect();
+
+        let (events, errors): (Vec<_>, Vec<_>) =
+            parse_events(SOURCE).partition(|event| !matches!(event, ParseEvent::Error(_)));
+        let events: Vec<_> =
+            events.into_iter().filter(|event| !matches!(event, ParseEvent::Finish)).collect();
+
+        assert_eq!(events, expected);
+        assert_eq!(
+            errors,
+            parse.errors().iter().cloned().map(ParseEvent::Error).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn validate_reports_parse_errors() {
+        assert!(validate("rule a { condition: true }").is_empty());
+        assert_eq!(validate(SOURCE), SourceFile::parse(SOURCE).errors());
+        assert!(!validate(SOURCE).is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+//! JSON format of the concrete syntax tree, for tools that are not written in Rust
+//!
+//! `Parse::to_json` writes the whole tree including trivia together with the errors
+//! and `Parse::from_json` rebuilds the identical green tree from it.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "tree": {
+//!     "kind": "SOURCE_FILE",
+//!     "range": [0, 26],
+//!     "children": [
+//!       { "kind": "RULE_KW", "range": [0, 4], "text": "rule" },
+//!       ...
+//!     ]
+//!   },
+//!   "errors": [
+//!     {
+//!       "message": "expected a name",
+//!       "range": [5, 5],
+//!       "suggestions": [{ "label": "...", "range": [5, 5], "replacement": "..." }]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Nodes have `children` and tokens have `text`, ranges are `[start, end]` byte offsets.
+//! `kind` is the name of the `SyntaxKind` variant as printed by `Debug`.
+//! Errors can also have `"severity": "warning"`, it is `"error"` when missing,
+//! and a `related` range. Missing `suggestions` are the same as an empty list.
+//!
+//! Versioning: `version` is `JSON_VERSION` and it is increased whenever a `SyntaxKind`
+//! is renamed or removed, or the tree shape of existing constructs changes,
+//! so older trees can no longer be read the same way. Adding new kinds does not change
+//! the version, neither do new optional fields. `from_json` accepts only the current version.
+
+use serde::{de::Error as _, Deserialize, Serialize};
+use text_size::{TextRange, TextSize};
+
+use crate::{
+    syntax::{
+        syntax_node::{SyntaxNode, SyntaxTreeBuilder},
+        Parse,
+    },
+    NodeOrToken, Severity, SourceFile, Suggestion, SyntaxError, SyntaxKind,
+};
+
+/// Version of the JSON format written by `Parse::to_json`
+pub const JSON_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct JsonParse {
+    version: u32,
+    tree: JsonElement,
+    errors: Vec<JsonError>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonElement {
+    kind: String,
+    range: TextRange,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    children: Option<Vec<JsonElement>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonError {
+    message: String,
+    range: TextRange,
+    #[serde(default, skip_serializing_if = "is_error")]
+    severity: JsonSeverity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    related: Option<TextRange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    suggestions: Vec<JsonSuggestion>,
+}
+
+#[derive(Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonSeverity {
+    #[default]
+    Error,
+    Warning,
+}
+
+fn is_error(severity: &JsonSeverity) -> bool {
+    *severity == JsonSeverity::Error
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonSuggestion {
+    label: String,
+    range: TextRange,
+    replacement: String,
+}
+
+impl Parse<SourceFile> {
+    /// Serialize the tree and the errors, see the module documentation for the format
+    pub fn to_json(&self) -> String {
+        let errors = self
+            .errors()
+            .iter()
+            .map(|error| JsonError {
+                message: error.message().to_string(),
+                range: error.range(),
+                severity: match error.severity() {
+                    Severity::Error => JsonSeverity::Error,
+                    Severity::Warning => JsonSeverity::Warning,
+                },
+                related: error.related(),
+                suggestions: error
+                    .suggestions()
+                    .iter()
+                    .map(|it| JsonSuggestion {
+                        label: it.label().to_string(),
+                        range: it.range(),
+                        replacement: it.replacement().to_string(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let json = JsonParse { version: JSON_VERSION, tree: element(&self.syntax_node()), errors };
+        serde_json::to_string(&json).expect("the tree is always serializable")
+    }
+
+    /// Rebuild the tree and the errors written by `to_json`
+    ///
+    /// Fails if the JSON is not valid, it has a different version, it contains unknown kinds,
+    /// the root is not `SOURCE_FILE` or the ranges do not match the text of the tokens.
+    pub fn from_json(json: &str) -> Result<Parse<SourceFile>, serde_json::Error> {
+        let json: JsonParse = serde_json::from_str(json)?;
+        if json.version != JSON_VERSION {
+            let message =
+                format!("unsupported version {}, expected {}", json.version, JSON_VERSION);
+            return Err(serde_json::Error::custom(message));
+        }
+        if json.tree.kind != "SOURCE_FILE" {
+            return Err(serde_json::Error::custom("the root is not SOURCE_FILE"));
+        }
+        let mut builder = SyntaxTreeBuilder::default();
+        build(&mut builder, &json.tree, 0.into()).map_err(serde_json::Error::custom)?;
+        let (green, _) = builder.finish_raw();
+        let errors = json
+            .errors
+            .into_iter()
+            .map(|error| {
+                let severity = match error.severity {
+                    JsonSeverity::Error => Severity::Error,
+                    JsonSeverity::Warning => Severity::Warning,
+                };
+                let mut res = SyntaxError::new(error.message, error.range).with_severity(severity);
+                if let Some(related) = error.related {
+                    res = res.with_related(related);
+                }
+                error.suggestions.into_iter().fold(res, |error, it| {
+                    error.with_suggestion(Suggestion::new(it.label, it.range, it.replacement))
+                })
+            })
+            .collect();
+        Ok(Parse::new(green, errors))
+    }
+}
+
+fn element(node: &SyntaxNode) -> JsonElement {
+    let children = node
+        .children_with_tokens()
+        .map(|child| match child {
+            NodeOrToken::Node(node) => element(&node),
+            NodeOrToken::Token(token) => JsonElement {
+                kind: format!("{:?}", token.kind()),
+                range: token.text_range(),
+                text: Some(token.text().to_string()),
+                children: None,
+            },
+        })
+        .collect();
+    JsonElement {
+        kind: format!("{:?}", node.kind()),
+        range: node.text_range(),
+        text: None,
+        children: Some(children),
+    }
+}
+
+/// Add the element to the tree, `offset` is where it has to start
+/// Returns the offset after the element
+fn build(
+    builder: &mut SyntaxTreeBuilder,
+    element: &JsonElement,
+    offset: TextSize,
+) -> Result<TextSize, String> {
+    let kind = SyntaxKind::from_name(&element.kind)
+        .ok_or_else(|| format!("unknown kind `{}`", element.kind))?;
+    let end = match (&element.text, &element.children) {
+        (Some(text), None) => {
+            builder.token(kind, text);
+            offset + TextSize::of(text.as_str())
+        }
+        (None, Some(children)) => {
+            builder.start_node(kind);
+            let end = children.iter().try_fold(offset, |offset, it| build(builder, it, offset))?;
+            builder.finish_node();
+            end
+        }
+        _ => return Err(format!("{} must have either `text` or `children`", element.kind)),
+    };
+    if element.range != TextRange::new(offset, end) {
+        return Err(format!(
+            "{} has range {:?}, but its text is at {:?}",
+            element.kind,
+            element.range,
+            TextRange::new(offset, end)
+        ));
+    }
+    Ok(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let parse = SourceFile::parse("rule a { // c\n strings: $a = \"\\\"a\" condtion: $a }");
+        let json = parse.to_json();
+        assert!(json.starts_with(
+            r#"{"version":1,"tree":{"kind":"SOURCE_FILE","range":[0,49],"children":[{"kind":"RULE","range":[0,49],"children":[{"kind":"RULE_KW","range":[0,4],"text":"rule"}"#
+        ));
+        let new = Parse::from_json(&json).unwrap();
+        assert_eq!(new.syntax_node().green(), parse.syntax_node().green());
+        assert_eq!(new.errors(), parse.errors());
+        assert!(!new.errors()[0].suggestions().is_empty());
+
+        let range = TextRange::new(5.into(), 6.into());
+        let warning = SyntaxError::new("warning", range)
+            .with_severity(Severity::Warning)
+            .with_related(TextRange::empty(0.into()));
+        let parse = Parse::<SourceFile>::new(parse.syntax_node().green().into(), vec![warning]);
+        let json = parse.to_json();
+        assert!(json.ends_with(
+            r#""errors":[{"message":"warning","range":[5,6],"severity":"warning","related":[0,0]}]}"#
+        ));
+        assert_eq!(Parse::from_json(&json).unwrap().errors(), parse.errors());
+    }
+
+    #[test]
+    fn invalid_json() {
+        let error = |json: &str| Parse::from_json(json).err().unwrap().to_string();
+        let json = |version: u32, tree: &str| {
+            format!(r#"{{"version":{},"tree":{},"errors":[]}}"#, version, tree)
+        };
+        let token = r#"{"kind":"RULE_KW","range":[0,4],"text":"rule"}"#;
+
+        assert_eq!(error(&json(2, token)), "unsupported version 2, expected 1");
+        assert_eq!(error(&json(1, token)), "the root is not SOURCE_FILE");
+        assert_eq!(
+            error(&json(
+                1,
+                r#"{"kind":"SOURCE_FILE","range":[0,0],"children":[{"kind":"RULES","range":[0,0],"children":[]}]}"#
+            )),
+            "unknown kind `RULES`"
+        );
+        assert_eq!(
+            error(&json(
+                1,
+                &format!(r#"{{"kind":"SOURCE_FILE","range":[0,5],"children":[{}]}}"#, token)
+            )),
+            "SOURCE_FILE has range 0..5, but its text is at 0..4"
+        );
+        assert_eq!(
+            error(&json(1, r#"{"kind":"SOURCE_FILE","range":[0,0]}"#)),
+            "SOURCE_FILE must have either `text` or `children`"
+        );
+        assert!(error("{").starts_with("EOF while parsing"));
+    }
+}
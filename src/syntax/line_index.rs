@@ -0,0 +1,280 @@
+//! Conversion between text offsets and line/column positions
+//!
+//! Lines can be terminated by `\n`, `\r\n` or a lone `\r`, `\r\n` is always
+//! treated as a single line break. Lines and columns are zero-based.
+//! Columns are counted in UTF-8 code units (bytes) by default, `WideEncoding`
+//! can be used to convert them to UTF-16 or UTF-32 code units, as required
+//! for example by the Language Server Protocol.
+
+use std::collections::HashMap;
+
+use text_size::{TextRange, TextSize};
+
+/// Line and column in UTF-8 code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// Encoding used for columns of `WideLineCol`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WideEncoding {
+    Utf16,
+    Utf32,
+}
+
+/// Line and column in code units of some `WideEncoding`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WideLineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// A character that takes more than one byte in UTF-8
+/// Range is relative to the start of the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WideChar {
+    start: TextSize,
+    end: TextSize,
+}
+
+impl WideChar {
+    fn len(&self) -> u32 {
+        u32::from(self.end - self.start)
+    }
+
+    fn wide_len(&self, enc: WideEncoding) -> u32 {
+        match enc {
+            // Only characters outside of BMP (4 bytes in UTF-8) need a surrogate pair
+            WideEncoding::Utf16 if self.len() == 4 => 2,
+            WideEncoding::Utf16 | WideEncoding::Utf32 => 1,
+        }
+    }
+}
+
+/// Maps text offsets to lines and columns and back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Offset of the start of each line
+    line_starts: Vec<TextSize>,
+    /// Multi-byte characters for each line that contains them
+    wide_chars: HashMap<u32, Vec<WideChar>>,
+    len: TextSize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> LineIndex {
+        let mut line_starts = vec![0.into()];
+        let mut wide_chars = HashMap::new();
+        let mut line_wide_chars = Vec::new();
+        let mut line_start = TextSize::from(0);
+
+        let mut chars = text.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            let pos = TextSize::try_from(pos).unwrap();
+            let c_len = TextSize::of(c);
+
+            let is_line_break = match c {
+                '\n' => true,
+                // `\r\n` is a single line break
+                '\r' => !matches!(chars.peek(), Some((_, '\n'))),
+                _ => false,
+            };
+            if is_line_break {
+                if !line_wide_chars.is_empty() {
+                    let line = line_starts.len() as u32 - 1;
+                    wide_chars.insert(line, std::mem::take(&mut line_wide_chars));
+                }
+                line_start = pos + c_len;
+                line_starts.push(line_start);
+            } else if c.len_utf8() > 1 {
+                let start = pos - line_start;
+                line_wide_chars.push(WideChar { start, end: start + c_len });
+            }
+        }
+        if !line_wide_chars.is_empty() {
+            wide_chars.insert(line_starts.len() as u32 - 1, line_wide_chars);
+        }
+
+        LineIndex { line_starts, wide_chars, len: TextSize::of(text) }
+    }
+
+    /// Length of the indexed text
+    pub fn len(&self) -> TextSize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0.into()
+    }
+
+    /// Number of lines, a text always has at least one line
+    pub fn line_count(&self) -> u32 {
+        self.line_starts.len() as u32
+    }
+
+    /// Range of the given line, including its line break
+    pub fn line(&self, line: u32) -> Option<TextRange> {
+        let start = *self.line_starts.get(line as usize)?;
+        let end = self.line_starts.get(line as usize + 1).copied().unwrap_or(self.len);
+        Some(TextRange::new(start, end))
+    }
+
+    /// Convert an offset into line and column
+    ///
+    /// Panics if the offset is out of bounds, use `try_line_col` for
+    /// a fallible version.
+    pub fn line_col(&self, offset: TextSize) -> LineCol {
+        self.try_line_col(offset)
+            .unwrap_or_else(|| panic!("offset {:?} is out of bounds {:?}", offset, self.len))
+    }
+
+    /// Convert an offset into line and column
+    pub fn try_line_col(&self, offset: TextSize) -> Option<LineCol> {
+        if offset > self.len {
+            return None;
+        }
+        let line = self.line_starts.partition_point(|&it| it <= offset) - 1;
+        let col = offset - self.line_starts[line];
+        Some(LineCol { line: line as u32, col: col.into() })
+    }
+
+    /// Convert line and column into an offset
+    /// Returns `None` if the position is not in the text
+    /// or it points inside of a character
+    pub fn offset(&self, line_col: LineCol) -> Option<TextSize> {
+        let line = self.line(line_col.line)?;
+        let offset = line.start().checked_add(TextSize::from(line_col.col))?;
+        if offset > line.end() {
+            return None;
+        }
+        let col = TextSize::from(line_col.col);
+        let inside_char = self
+            .wide_chars
+            .get(&line_col.line)
+            .is_some_and(|chars| chars.iter().any(|c| c.start < col && col < c.end));
+        if inside_char {
+            return None;
+        }
+        Some(offset)
+    }
+
+    /// Convert UTF-8 based line and column to the given encoding
+    pub fn to_wide(&self, enc: WideEncoding, line_col: LineCol) -> Option<WideLineCol> {
+        self.offset(line_col)?;
+        let mut col = line_col.col;
+        if let Some(chars) = self.wide_chars.get(&line_col.line) {
+            for c in chars.iter().take_while(|c| c.end <= TextSize::from(line_col.col)) {
+                col -= c.len() - c.wide_len(enc);
+            }
+        }
+        Some(WideLineCol { line: line_col.line, col })
+    }
+
+    /// Convert line and column in the given encoding into UTF-8 based one
+    pub fn to_utf8(&self, enc: WideEncoding, line_col: WideLineCol) -> Option<LineCol> {
+        let mut col = line_col.col;
+        if let Some(chars) = self.wide_chars.get(&line_col.line) {
+            for c in chars {
+                if col <= u32::from(c.start) {
+                    break;
+                }
+                if col < u32::from(c.start) + c.wide_len(enc) {
+                    // Points inside of a surrogate pair
+                    return None;
+                }
+                col = col.checked_add(c.len() - c.wide_len(enc))?;
+            }
+        }
+        let res = LineCol { line: line_col.line, col };
+        self.offset(res)?;
+        Some(res)
+    }
+
+    /// Convert an offset directly into line and column in the given encoding
+    pub fn wide_line_col(&self, enc: WideEncoding, offset: TextSize) -> Option<WideLineCol> {
+        self.to_wide(enc, self.try_line_col(offset)?)
+    }
+
+    /// Convert line and column in the given encoding directly into an offset
+    pub fn wide_offset(&self, enc: WideEncoding, line_col: WideLineCol) -> Option<TextSize> {
+        self.offset(self.to_utf8(enc, line_col)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceFile;
+
+    #[test]
+    fn line_breaks() {
+        let text = "rule a\r\n{\rcondition:\n true }";
+        let index = LineIndex::new(text);
+        assert_eq!(index.line_count(), 4);
+
+        assert_eq!(index.line_col(0.into()), LineCol { line: 0, col: 0 });
+        assert_eq!(index.line_col(6.into()), LineCol { line: 0, col: 6 });
+        assert_eq!(index.line_col(8.into()), LineCol { line: 1, col: 0 });
+        assert_eq!(index.line_col(10.into()), LineCol { line: 2, col: 0 });
+        assert_eq!(index.line_col(21.into()), LineCol { line: 3, col: 0 });
+        assert_eq!(index.line_col(TextSize::of(text)), LineCol { line: 3, col: 7 });
+        assert_eq!(index.try_line_col(TextSize::of(text) + TextSize::from(1)), None);
+
+        assert_eq!(index.line(0), Some(TextRange::new(0.into(), 8.into())));
+        assert_eq!(index.line(3), Some(TextRange::new(21.into(), 28.into())));
+        assert_eq!(index.line(4), None);
+
+        for offset in 0..=text.len() as u32 {
+            let line_col = index.line_col(offset.into());
+            assert_eq!(index.offset(line_col), Some(offset.into()));
+        }
+        assert_eq!(index.offset(LineCol { line: 1, col: 5 }), None);
+        assert_eq!(index.offset(LineCol { line: 3, col: u32::MAX }), None);
+        let wide = WideLineCol { line: 3, col: u32::MAX };
+        assert_eq!(index.wide_offset(WideEncoding::Utf16, wide), None);
+    }
+
+    #[test]
+    fn wide_columns() {
+        // 'č' is 2 bytes in UTF-8, '😀' is 4 bytes in UTF-8 and 2 code units in UTF-16
+        let text = "// č😀\n$a = \"😀\" // x";
+        let index = LineIndex::new(text);
+
+        let x = TextSize::try_from(text.rfind('x').unwrap()).unwrap();
+        assert_eq!(index.line_col(x), LineCol { line: 1, col: 15 });
+        assert_eq!(
+            index.wide_line_col(WideEncoding::Utf16, x),
+            Some(WideLineCol { line: 1, col: 13 })
+        );
+        assert_eq!(
+            index.wide_line_col(WideEncoding::Utf32, x),
+            Some(WideLineCol { line: 1, col: 12 })
+        );
+
+        let end = LineCol { line: 0, col: 9 };
+        assert_eq!(index.to_wide(WideEncoding::Utf16, end), Some(WideLineCol { line: 0, col: 6 }));
+        assert_eq!(index.to_wide(WideEncoding::Utf32, end), Some(WideLineCol { line: 0, col: 5 }));
+
+        for enc in [WideEncoding::Utf16, WideEncoding::Utf32] {
+            for (offset, _) in text.char_indices() {
+                let offset = TextSize::try_from(offset).unwrap();
+                let wide = index.wide_line_col(enc, offset).unwrap();
+                assert_eq!(index.wide_offset(enc, wide), Some(offset));
+            }
+        }
+
+        // Inside of a multi-byte character or a surrogate pair
+        assert_eq!(index.offset(LineCol { line: 0, col: 4 }), None);
+        assert_eq!(index.to_utf8(WideEncoding::Utf16, WideLineCol { line: 0, col: 5 }), None);
+    }
+
+    #[test]
+    fn parse_line_index() {
+        let parse = SourceFile::parse("rule a {\r\n  condition:\r\n    $a nor\r\n}");
+        let error = &parse.errors()[0];
+        let index = parse.line_index();
+        assert_eq!(index.line_col(error.range().start()), LineCol { line: 2, col: 7 });
+    }
+}
@@ -16,7 +16,15 @@
 //! More detailed information can be also found in `rust-analyzer` syntax documentation
 //! [Rust-analyzer]: <https://github.com/rust-lang/rust-analyzer/blob/4b7675fcc30d3e2c05eafc68a5724db66b58142c/docs/dev/syntax.md>
 
+pub mod algo;
 pub mod ast;
+pub mod events;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod line_index;
+pub mod printer;
+pub mod ptr;
+pub mod structural;
 pub mod syntax_error;
 pub mod syntax_node;
 pub mod text_token_source;
@@ -25,13 +33,21 @@ pub mod text_tree_sink;
 mod tests;
 
 pub use rowan_test::GreenNode;
-use std::{marker::PhantomData, sync::Arc};
+use std::{
+    marker::PhantomData,
+    sync::{Arc, OnceLock},
+};
 
 use crate::{
-    lexer::tokenize,
-    parser::{self, SyntaxKind},
-    syntax::{ast::AstNode, syntax_node::SyntaxNode},
-    SyntaxError, TextTokenSource, TextTreeSink,
+    parser::SyntaxKind,
+    syntax::{
+        ast::AstNode,
+        events::parse_with,
+        line_index::LineIndex,
+        printer::{to_dot, to_outline, to_sexpr, PrintOptions},
+        syntax_node::{SyntaxNode, SyntaxTreeBuilder},
+    },
+    SyntaxError,
 };
 
 macro_rules! format_to {
@@ -44,33 +60,78 @@ macro_rules! format_to {
 /// A result of a successful parsing of a source file.
 /// It provides AST and list of errors.
 /// We always produce a syntax tree, even for invalid files.
+///
+/// `Parse` is cheap to clone and it is `Send + Sync`, so it can be shared
+/// between threads. Use `SyntaxNodePtr` to refer to nodes across threads.
 pub struct Parse<T> {
     green: GreenNode,
     errors: Arc<Vec<SyntaxError>>,
+    line_index: Arc<OnceLock<LineIndex>>,
     _ty: PhantomData<fn() -> T>,
 }
 
 impl<T> Clone for Parse<T> {
     fn clone(&self) -> Parse<T> {
-        Parse { green: self.green.clone(), errors: self.errors.clone(), _ty: PhantomData }
+        Parse {
+            green: self.green.clone(),
+            errors: self.errors.clone(),
+            line_index: self.line_index.clone(),
+            _ty: PhantomData,
+        }
     }
 }
 
 impl<T> Parse<T> {
-    #[allow(dead_code)]
     fn new(green: GreenNode, errors: Vec<SyntaxError>) -> Parse<T> {
-        Parse { green, errors: Arc::new(errors), _ty: PhantomData }
+        Parse {
+            green,
+            errors: Arc::new(errors),
+            line_index: Arc::new(OnceLock::new()),
+            _ty: PhantomData,
+        }
+    }
+
+    /// Change the type of the `Parse`, the caller has to ensure that
+    /// the root node can be cast to `U`
+    fn retype<U>(self) -> Parse<U> {
+        Parse {
+            green: self.green,
+            errors: self.errors,
+            line_index: self.line_index,
+            _ty: PhantomData,
+        }
     }
 
     pub fn syntax_node(&self) -> SyntaxNode {
         SyntaxNode::new_root(self.green.clone())
     }
+
+    /// Line index of the parsed text
+    /// It is built on the first call and shared by all clones of this `Parse`
+    pub fn line_index(&self) -> &LineIndex {
+        self.line_index.get_or_init(|| LineIndex::new(&self.syntax_node().text().to_string()))
+    }
+
+    /// Print the tree as a single-line S-expression, see `printer::to_sexpr`
+    pub fn to_sexpr(&self, options: &PrintOptions) -> String {
+        to_sexpr(&self.syntax_node(), options)
+    }
+
+    /// Print the tree as an indented outline, see `printer::to_outline`
+    pub fn to_outline(&self, options: &PrintOptions) -> String {
+        to_outline(&self.syntax_node(), options)
+    }
+
+    /// Export the tree as a Graphviz graph, see `printer::to_dot`
+    pub fn to_dot(&self, options: &PrintOptions) -> String {
+        to_dot(&self.syntax_node(), options)
+    }
 }
 
 impl<T: AstNode> Parse<T> {
     #[allow(clippy::wrong_self_convention)]
     pub fn to_syntax(self) -> Parse<SyntaxNode> {
-        Parse { green: self.green, errors: self.errors, _ty: PhantomData }
+        self.retype()
     }
 
     pub fn tree(&self) -> T {
@@ -93,7 +154,7 @@ impl<T: AstNode> Parse<T> {
 impl Parse<SyntaxNode> {
     pub fn cast<N: AstNode>(self) -> Option<Parse<N>> {
         if N::cast(self.syntax_node()).is_some() {
-            Some(Parse { green: self.green, errors: self.errors, _ty: PhantomData })
+            Some(self.retype())
         } else {
             None
         }
@@ -121,19 +182,13 @@ impl SourceFile {
         let root = SyntaxNode::new_root(green.clone());
 
         assert_eq!(root.kind(), SyntaxKind::SOURCE_FILE);
-        Parse { green, errors: Arc::new(errors), _ty: PhantomData }
+        Parse::new(green, errors)
     }
 }
 
 /// Parses the given string representation of file into a syntax tree.
 fn parse_text(text: &str) -> (GreenNode, Vec<SyntaxError>) {
-    let (tokens, lexer_errors) = tokenize(text);
-    let mut token_source = TextTokenSource::new(text, &tokens);
-    let mut tree_sink = TextTreeSink::new(text, &tokens);
-
-    parser::parse(&mut token_source, &mut tree_sink);
-    let (tree, mut parser_errors) = tree_sink.finish();
-    parser_errors.extend(lexer_errors);
-
-    (tree, parser_errors)
+    let mut builder = SyntaxTreeBuilder::default();
+    parse_with(text, &mut builder);
+    builder.finish_raw()
 }
@@ -0,0 +1,226 @@
+//! Printers of the syntax tree in formats that are easier to read than `{:#?}`
+//!
+//! - `to_sexpr` prints the tree as a compact S-expression,
+//!   nodes are `(KIND children...)` and tokens are their quoted text
+//! - `to_outline` prints one element per line, indented by its depth
+//! - `to_dot` exports the tree as a Graphviz graph, error nodes are drawn in red
+//!
+//! `PrintOptions` controls which elements and details are printed.
+
+use std::fmt::Write;
+
+use rowan_test::NodeOrToken;
+
+use crate::{
+    syntax::syntax_node::{SyntaxElement, SyntaxNode},
+    SyntaxKind,
+};
+
+/// Options shared by all printers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintOptions {
+    /// Print whitespace and comments
+    pub trivia: bool,
+    /// Print text ranges of nodes and tokens
+    pub ranges: bool,
+    /// Print `ERROR` nodes with their content
+    pub errors: bool,
+}
+
+impl Default for PrintOptions {
+    /// Everything except trivia and ranges
+    fn default() -> Self {
+        Self { trivia: false, ranges: false, errors: true }
+    }
+}
+
+impl PrintOptions {
+    fn is_visible(&self, element: &SyntaxElement) -> bool {
+        match element.kind() {
+            SyntaxKind::ERROR => self.errors,
+            kind => self.trivia || !kind.is_trivia(),
+        }
+    }
+
+    fn children(&self, node: &SyntaxNode) -> impl Iterator<Item = SyntaxElement> + '_ {
+        node.children_with_tokens().filter(|it| self.is_visible(it))
+    }
+}
+
+/// Print the tree as a single-line S-expression
+///
+/// `rule a { condition: true }` is printed as
+/// `(RULE "rule" "a" (BLOCK_EXPR "{" (CONDITION "condition" ":" ...) "}"))`.
+/// With ranges, each element is followed by `@start..end`.
+pub fn to_sexpr(node: &SyntaxNode, options: &PrintOptions) -> String {
+    fn go(buf: &mut String, element: &SyntaxElement, options: &PrintOptions) {
+        match element {
+            NodeOrToken::Node(node) => {
+                let _ = write!(buf, "({:?}", node.kind());
+                if options.ranges {
+                    let _ = write!(buf, "@{:?}", node.text_range());
+                }
+                for child in options.children(node) {
+                    buf.push(' ');
+                    go(buf, &child, options);
+                }
+                buf.push(')');
+            }
+            NodeOrToken::Token(token) => {
+                let _ = write!(buf, "{:?}", token.text());
+                if options.ranges {
+                    let _ = write!(buf, "@{:?}", token.text_range());
+                }
+            }
+        }
+    }
+
+    let mut buf = String::new();
+    go(&mut buf, &node.clone().into(), options);
+    buf
+}
+
+/// Print the tree with one element per line, indented by two spaces per level
+///
+/// Nodes are printed as their kind, tokens as their kind and quoted text.
+/// With ranges, the kind is followed by `@start..end`.
+pub fn to_outline(node: &SyntaxNode, options: &PrintOptions) -> String {
+    fn go(buf: &mut String, element: &SyntaxElement, depth: usize, options: &PrintOptions) {
+        let _ = write!(buf, "{:indent$}{:?}", "", element.kind(), indent = depth * 2);
+        if options.ranges {
+            let _ = write!(buf, "@{:?}", element.text_range());
+        }
+        match element {
+            NodeOrToken::Node(node) => {
+                buf.push('\n');
+                for child in options.children(node) {
+                    go(buf, &child, depth + 1, options);
+                }
+            }
+            NodeOrToken::Token(token) => {
+                let _ = writeln!(buf, " {:?}", token.text());
+            }
+        }
+    }
+
+    let mut buf = String::new();
+    go(&mut buf, &node.clone().into(), 0, options);
+    buf
+}
+
+/// Export the tree as a Graphviz `digraph`
+///
+/// Nodes are drawn as boxes, tokens as plain text with their kind and text
+/// and `ERROR` nodes are drawn in red. With ranges, the label has a second line
+/// with the range.
+pub fn to_dot(node: &SyntaxNode, options: &PrintOptions) -> String {
+    fn go(buf: &mut String, element: &SyntaxElement, next_id: &mut usize, options: &PrintOptions) {
+        let id = *next_id;
+        *next_id += 1;
+        let mut label = format!("{:?}", element.kind());
+        if let NodeOrToken::Token(token) = element {
+            let _ = write!(label, " {:?}", token.text());
+        }
+        if options.ranges {
+            let _ = write!(label, "\n{:?}", element.text_range());
+        }
+        let style = match element {
+            NodeOrToken::Node(node) if node.kind() == SyntaxKind::ERROR => ", color=red",
+            NodeOrToken::Node(_) => "",
+            NodeOrToken::Token(_) => ", shape=plaintext",
+        };
+        let _ = writeln!(buf, "    n{} [label=\"{}\"{}];", id, escape(&label), style);
+        if let NodeOrToken::Node(node) = element {
+            for child in options.children(node) {
+                let _ = writeln!(buf, "    n{} -> n{};", id, *next_id);
+                go(buf, &child, next_id, options);
+            }
+        }
+    }
+
+    let mut buf = String::from("digraph {\n    node [shape=box];\n");
+    go(&mut buf, &node.clone().into(), &mut 0, options);
+    buf.push_str("}\n");
+    buf
+}
+
+/// Escape the text for a quoted DOT string
+fn escape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceFile;
+
+    #[test]
+    fn sexpr() {
+        let parse = SourceFile::parse("rule a { // c\n condition: true }");
+        let node = parse.syntax_node();
+        assert_eq!(
+            to_sexpr(&node, &PrintOptions::default()),
+            r#"(SOURCE_FILE (RULE "rule" "a" (BLOCK_EXPR "{" (CONDITION "condition" ":" (EXPRESSION_STMT (BOOLEAN_TERM "true"))) "}")))"#
+        );
+        let options = PrintOptions { trivia: true, ranges: true, ..Default::default() };
+        let sexpr = to_sexpr(&node, &options);
+        assert!(
+            sexpr.starts_with(r#"(SOURCE_FILE@0..32 (RULE@0..32 "rule"@0..4 " "@4..5 "a"@5..6"#)
+        );
+        assert!(sexpr.contains(r#""// c"@9..13"#));
+    }
+
+    #[test]
+    fn outline() {
+        let parse = SourceFile::parse("rule \"a\" {}");
+        let options = PrintOptions { ranges: true, ..Default::default() };
+        assert_eq!(
+            parse.to_outline(&options),
+            r#"SOURCE_FILE@0..11
+  RULE@0..11
+    RULE_KW@0..4 "rule"
+    ERROR@5..8
+      STRING_LIT@5..8 "\"a\""
+    BLOCK_EXPR@9..11
+      L_BRACE@9..10 "{"
+      R_BRACE@10..11 "}"
+"#
+        );
+        let options = PrintOptions { trivia: true, errors: false, ..Default::default() };
+        assert_eq!(
+            parse.to_outline(&options),
+            r#"SOURCE_FILE
+  RULE
+    RULE_KW "rule"
+    WHITESPACE " "
+    WHITESPACE " "
+    BLOCK_EXPR
+      L_BRACE "{"
+      R_BRACE "}"
+"#
+        );
+    }
+
+    #[test]
+    fn dot() {
+        let parse = SourceFile::parse("rule \"a\" {}");
+        let dot = parse.to_dot(&PrintOptions { ranges: true, ..Default::default() });
+        assert!(dot.starts_with(
+            "digraph {\n    node [shape=box];\n    n0 [label=\"SOURCE_FILE\\n0..11\"];\n"
+        ));
+        assert!(dot.contains("[label=\"ERROR\\n5..8\", color=red];"));
+        assert!(dot
+            .contains("[label=\"STRING_LIT \\\"\\\\\\\"a\\\\\\\"\\\"\\n5..8\", shape=plaintext];"));
+        assert!(dot.contains("    n0 -> n1;\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,323 @@
+//! Thread-safe handles to syntax nodes
+//!
+//! `SyntaxNode` is a cursor into a tree and it is not `Send`, so it can't be moved
+//! to another thread. The underlying `GreenNode` (and therefore `Parse`) is immutable
+//! and atomically reference counted, so it can be shared freely between threads.
+//!
+//! `SyntaxNodePtr` identifies a node by its kind and range. It can be stored,
+//! sent to other threads and resolved back to a `SyntaxNode` against the root
+//! of the tree it was created from. `AstPtr` is its typed counterpart.
+//!
+//! Pointers don't hold any part of the tree, so they can be resolved
+//! against any other parse of the same text (or generally any tree in which the node
+//! kept its kind and position). This makes them usable as stable keys for caching
+//! analysis results.
+
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    iter::successors,
+    marker::PhantomData,
+};
+
+use text_size::TextRange;
+
+use crate::{
+    parser::SyntaxKind,
+    syntax::{ast::AstNode, syntax_node::SyntaxNode},
+};
+
+/// A pointer to a syntax node inside a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SyntaxNodePtr {
+    kind: SyntaxKind,
+    range: TextRange,
+    /// Parser can produce directly nested nodes with the same kind and range
+    /// (for example `BOOLEAN_EXPR` in `BOOLEAN_EXPR`). This is the number
+    /// of such ancestors above the pointed node.
+    depth: u32,
+}
+
+impl SyntaxNodePtr {
+    /// Create a pointer to the given node
+    pub fn new(node: &SyntaxNode) -> Self {
+        let kind = node.kind();
+        let range = node.text_range();
+        let depth = successors(node.parent(), |it| it.parent())
+            .take_while(|it| it.kind() == kind && it.text_range() == range)
+            .count() as u32;
+        SyntaxNodePtr { kind, range, depth }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        self.range
+    }
+
+    /// Resolve the pointer against the `root` of the tree
+    ///
+    /// Panics if the node does not exist in this tree,
+    /// use `try_to_node` for a fallible version.
+    pub fn to_node(&self, root: &SyntaxNode) -> SyntaxNode {
+        self.try_to_node(root)
+            .unwrap_or_else(|| panic!("can't resolve local ptr to SyntaxNode: {:?}", self))
+    }
+
+    /// Resolve the pointer against the `root` of the tree
+    /// Returns `None` if the tree does not contain such node
+    pub fn try_to_node(&self, root: &SyntaxNode) -> Option<SyntaxNode> {
+        let mut node = self.find_outermost(root)?;
+        for _ in 0..self.depth {
+            node = node
+                .children()
+                .find(|it| it.kind() == self.kind && it.text_range() == self.range)?;
+        }
+        Some(node)
+    }
+
+    /// Find the outermost node with matching kind and range
+    fn find_outermost(&self, node: &SyntaxNode) -> Option<SyntaxNode> {
+        if node.kind() == self.kind && node.text_range() == self.range {
+            return Some(node.clone());
+        }
+        // More than one child can contain an empty range,
+        // so we need to search all of them
+        node.children()
+            .filter(|it| it.text_range().contains_range(self.range))
+            .find_map(|it| self.find_outermost(&it))
+    }
+}
+
+/// Like `SyntaxNodePtr`, but remembers the type of the node
+pub struct AstPtr<N: AstNode> {
+    raw: SyntaxNodePtr,
+    _ty: PhantomData<fn() -> N>,
+}
+
+impl<N: AstNode> AstPtr<N> {
+    /// Create a pointer to the given node
+    pub fn new(node: &N) -> AstPtr<N> {
+        AstPtr { raw: SyntaxNodePtr::new(node.syntax()), _ty: PhantomData }
+    }
+
+    /// Create a typed pointer from the untyped one,
+    /// returns `None` if the kind does not match `N`
+    pub fn try_from_raw(raw: SyntaxNodePtr) -> Option<AstPtr<N>> {
+        N::can_cast(raw.kind()).then_some(AstPtr { raw, _ty: PhantomData })
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.raw.kind()
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        self.raw.text_range()
+    }
+
+    pub fn syntax_node_ptr(&self) -> SyntaxNodePtr {
+        self.raw
+    }
+
+    /// Resolve the pointer against the `root` of the tree
+    ///
+    /// Panics if the node does not exist in this tree,
+    /// use `try_to_node` for a fallible version.
+    pub fn to_node(&self, root: &SyntaxNode) -> N {
+        N::cast(self.raw.to_node(root)).unwrap()
+    }
+
+    /// Resolve the pointer against the `root` of the tree
+    /// Returns `None` if the tree does not contain such node
+    pub fn try_to_node(&self, root: &SyntaxNode) -> Option<N> {
+        self.raw.try_to_node(root).and_then(N::cast)
+    }
+
+    /// Cast the pointer to another node type, for example from an enum to one of its variants
+    pub fn cast<U: AstNode>(self) -> Option<AstPtr<U>> {
+        AstPtr::try_from_raw(self.raw)
+    }
+
+    /// Convert the pointer to a type that contains this node type, for example to an enum
+    pub fn upcast<M: AstNode>(self) -> AstPtr<M>
+    where
+        N: Into<M>,
+    {
+        AstPtr { raw: self.raw, _ty: PhantomData }
+    }
+}
+
+impl<N: AstNode> fmt::Debug for AstPtr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AstPtr").field(&self.raw).finish()
+    }
+}
+
+impl<N: AstNode> Clone for AstPtr<N> {
+    fn clone(&self) -> AstPtr<N> {
+        *self
+    }
+}
+
+impl<N: AstNode> Copy for AstPtr<N> {}
+
+impl<N: AstNode> PartialEq for AstPtr<N> {
+    fn eq(&self, other: &AstPtr<N>) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<N: AstNode> Eq for AstPtr<N> {}
+
+impl<N: AstNode> Hash for AstPtr<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state)
+    }
+}
+
+impl<N: AstNode> From<AstPtr<N>> for SyntaxNodePtr {
+    fn from(ptr: AstPtr<N>) -> SyntaxNodePtr {
+        ptr.raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc, thread};
+
+    use super::*;
+    use crate::{
+        syntax::{ast, GreenNode},
+        AstNode, Parse, SourceFile,
+    };
+
+    const SOURCE: &str = r#"
+        rule first {
+            strings:
+                $a = "foo"
+                $b = { AB CD }
+            condition:
+                $a or $b and true
+        }
+
+        rule second {
+            condition:
+                first and filesize < 100
+        }
+    "#;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn shareable_types() {
+        assert_send_sync::<Parse<SourceFile>>();
+        assert_send_sync::<GreenNode>();
+        assert_send_sync::<SyntaxNodePtr>();
+        assert_send_sync::<AstPtr<ast::Rule>>();
+    }
+
+    #[test]
+    fn resolve_after_reparse() {
+        let parse = SourceFile::parse(SOURCE);
+        let mut unused: HashMap<AstPtr<ast::VariableStmt>, &str> = HashMap::new();
+        for stmt in parse.tree().syntax().descendants().filter_map(ast::VariableStmt::cast) {
+            unused.insert(AstPtr::new(&stmt), "unused");
+        }
+        let rule = AstPtr::new(&parse.tree().rules().nth(1).unwrap());
+        drop(parse);
+
+        // Cached pointers can be resolved against a fresh parse on another thread
+        let reparsed = SourceFile::parse(SOURCE);
+        thread::scope(|s| {
+            s.spawn(|| {
+                let root = reparsed.syntax_node();
+                let mut names: Vec<_> = unused
+                    .keys()
+                    .map(|ptr| ptr.to_node(&root).variable_token().unwrap().text().to_string())
+                    .collect();
+                names.sort();
+                assert_eq!(names, ["$a", "$b"]);
+                assert_eq!(rule.to_node(&root).identifier_token().unwrap().text(), "second");
+            });
+        });
+
+        // Edits after the node keep the pointer valid, edits before it don't
+        let appended = SourceFile::parse(&format!("{}rule third {{ condition: true }}", SOURCE));
+        assert!(rule.try_to_node(&appended.syntax_node()).is_some());
+        let prepended = SourceFile::parse(&format!("import \"pe\"{}", SOURCE));
+        assert!(rule.try_to_node(&prepended.syntax_node()).is_none());
+    }
+
+    #[test]
+    fn cast_ptr() {
+        let parse = SourceFile::parse(SOURCE);
+        let expr = parse.syntax_node().descendants().find_map(ast::BooleanExpr::cast).unwrap();
+        let ptr = AstPtr::new(&expr);
+
+        let upcasted: AstPtr<ast::Expression> = ptr.upcast();
+        assert_eq!(upcasted.syntax_node_ptr(), ptr.syntax_node_ptr());
+        assert_eq!(upcasted.cast::<ast::BooleanExpr>(), Some(ptr));
+        assert_eq!(upcasted.cast::<ast::Rule>(), None);
+        assert_eq!(AstPtr::<ast::Rule>::try_from_raw(ptr.into()), None);
+    }
+
+    #[test]
+    fn resolve_nested_nodes() {
+        let parse = SourceFile::parse(SOURCE);
+        let root = parse.syntax_node();
+        for node in root.descendants() {
+            let ptr = SyntaxNodePtr::new(&node);
+            assert_eq!(ptr.to_node(&root), node);
+        }
+    }
+
+    #[test]
+    fn analyze_on_multiple_threads() {
+        let parse = Arc::new(SourceFile::parse(SOURCE));
+
+        // Each worker runs a different analysis over the same parse
+        // and reports its results as pointers
+        let workers = vec![
+            thread::spawn({
+                let parse = Arc::clone(&parse);
+                move || {
+                    parse
+                        .tree()
+                        .syntax()
+                        .descendants()
+                        .filter_map(ast::VariableStmt::cast)
+                        .map(|it| SyntaxNodePtr::new(it.syntax()))
+                        .collect::<Vec<_>>()
+                }
+            }),
+            thread::spawn({
+                let parse = Arc::clone(&parse);
+                move || parse.tree().rules().map(|it| SyntaxNodePtr::new(it.syntax())).collect()
+            }),
+        ];
+        let results: Vec<Vec<SyntaxNodePtr>> =
+            workers.into_iter().map(|it| it.join().unwrap()).collect();
+
+        // Pointers can be resolved back to typed nodes on yet another thread
+        thread::scope(|s| {
+            s.spawn(|| {
+                let root = parse.syntax_node();
+                let patterns: Vec<_> = results[0]
+                    .iter()
+                    .map(|ptr| ast::VariableStmt::cast(ptr.to_node(&root)).unwrap())
+                    .map(|it| it.variable_token().unwrap().text().to_string())
+                    .collect();
+                assert_eq!(patterns, ["$a", "$b"]);
+
+                let rules: Vec<_> = results[1]
+                    .iter()
+                    .map(|ptr| ast::Rule::cast(ptr.to_node(&root)).unwrap())
+                    .map(|it| it.identifier_token().unwrap().text().to_string())
+                    .collect();
+                assert_eq!(rules, ["first", "second"]);
+            });
+        });
+    }
+}
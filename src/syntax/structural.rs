@@ -0,0 +1,179 @@
+//! Structural equality and hashing of syntax trees
+//!
+//! Two subtrees are structurally equal if they have the same nodes and tokens
+//! after whitespace and comments are removed. With `StructuralOptions::normalize_literals`,
+//! literals are compared by their value, so `0x10` equals `16`, `"\x41"` equals `"A"`
+//! and `4d` equals `4D` in hex patterns.
+//!
+//! Both operations walk the tree once and allocate only for normalized literals,
+//! so they are cheap enough to be used on every rule of a large corpus.
+
+use std::{
+    borrow::Cow,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use rowan_test::{NodeOrToken, WalkEvent};
+
+use crate::{
+    hir,
+    syntax::{
+        ast::AstNode,
+        syntax_node::{SyntaxNode, SyntaxToken},
+    },
+    SyntaxKind,
+};
+
+/// Options of the structural comparison
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StructuralOptions {
+    /// Compare integer, string and hex literals by their value instead of their spelling
+    pub normalize_literals: bool,
+}
+
+/// Comparison and hashing of syntax trees that ignore trivia
+///
+/// Nodes that are structurally equal have the same structural hash.
+/// The hash is stable for a given version of the library, but it should not be persisted
+/// across versions.
+pub trait StructuralEq {
+    fn structurally_eq_with(&self, other: &Self, options: &StructuralOptions) -> bool;
+
+    fn structural_hash_with(&self, options: &StructuralOptions) -> u64;
+
+    fn structurally_eq(&self, other: &Self) -> bool {
+        self.structurally_eq_with(other, &StructuralOptions::default())
+    }
+
+    fn structural_hash(&self) -> u64 {
+        self.structural_hash_with(&StructuralOptions::default())
+    }
+}
+
+impl StructuralEq for SyntaxNode {
+    fn structurally_eq_with(&self, other: &Self, options: &StructuralOptions) -> bool {
+        let mut lhs = events(self);
+        let mut rhs = events(other);
+        loop {
+            match (lhs.next(), rhs.next()) {
+                (None, None) => return true,
+                (Some(Event::Enter(lhs)), Some(Event::Enter(rhs))) if lhs == rhs => (),
+                (Some(Event::Leave), Some(Event::Leave)) => (),
+                (Some(Event::Token(lhs)), Some(Event::Token(rhs)))
+                    if lhs.kind() == rhs.kind()
+                        && token_text(&lhs, options) == token_text(&rhs, options) => {}
+                _ => return false,
+            }
+        }
+    }
+
+    fn structural_hash_with(&self, options: &StructuralOptions) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for event in events(self) {
+            match event {
+                Event::Enter(kind) => (0u8, kind).hash(&mut hasher),
+                Event::Leave => 1u8.hash(&mut hasher),
+                Event::Token(token) => {
+                    (2u8, token.kind(), token_text(&token, options)).hash(&mut hasher)
+                }
+            }
+        }
+        hasher.finish()
+    }
+}
+
+impl<N: AstNode> StructuralEq for N {
+    fn structurally_eq_with(&self, other: &Self, options: &StructuralOptions) -> bool {
+        self.syntax().structurally_eq_with(other.syntax(), options)
+    }
+
+    fn structural_hash_with(&self, options: &StructuralOptions) -> u64 {
+        self.syntax().structural_hash_with(options)
+    }
+}
+
+enum Event {
+    Enter(SyntaxKind),
+    Leave,
+    Token(SyntaxToken),
+}
+
+/// Preorder traversal without trivia
+fn events(node: &SyntaxNode) -> impl Iterator<Item = Event> {
+    node.preorder_with_tokens().filter_map(|event| match event {
+        WalkEvent::Enter(NodeOrToken::Node(node)) => Some(Event::Enter(node.kind())),
+        WalkEvent::Leave(NodeOrToken::Node(_)) => Some(Event::Leave),
+        WalkEvent::Enter(NodeOrToken::Token(token)) if !token.kind().is_trivia() => {
+            Some(Event::Token(token))
+        }
+        WalkEvent::Enter(NodeOrToken::Token(_)) | WalkEvent::Leave(NodeOrToken::Token(_)) => None,
+    })
+}
+
+/// Text of the token that is compared, literals are replaced by their value if requested
+fn token_text<'a>(token: &'a SyntaxToken, options: &StructuralOptions) -> Cow<'a, [u8]> {
+    let text = token.text();
+    let normalized = match token.kind() {
+        _ if !options.normalize_literals => None,
+        SyntaxKind::INT_LIT => hir::parse_int(text).map(|it| it.to_string().into_bytes()),
+        SyntaxKind::STRING_LIT => hir::unescape(text).ok(),
+        SyntaxKind::HEX_LIT => Some(text.to_ascii_uppercase().into_bytes()),
+        _ => None,
+    };
+    normalized.map_or(Cow::Borrowed(text.as_bytes()), Cow::Owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{syntax::ast, SourceFile};
+
+    fn rules(text: &str) -> Vec<ast::Rule> {
+        SourceFile::parse(text).tree().rules().collect()
+    }
+
+    #[test]
+    fn ignores_trivia() {
+        let rules = rules(
+            r#"
+rule a { strings: $a = "A" condition: $a and 16 > 0 }
+rule a {
+    // comment
+    strings:
+        $a = "A" /* hex */
+    condition:
+        $a and
+        16 > 0
+}
+rule a { strings: $a = "\x41" condition: $a and 0x10 > 0 }
+rule b { strings: $a = "A" condition: $a and 16 > 0 }
+"#,
+        );
+        assert!(rules[0].structurally_eq(&rules[1]));
+        assert_eq!(rules[0].structural_hash(), rules[1].structural_hash());
+        assert!(!rules[0].structurally_eq(&rules[2]));
+        assert!(!rules[0].structurally_eq(&rules[3]));
+        assert_ne!(rules[0].structural_hash(), rules[3].structural_hash());
+        assert!(rules[0].syntax().structurally_eq(rules[1].syntax()));
+    }
+
+    #[test]
+    fn normalize_literals() {
+        let rules = rules(
+            r#"
+rule a { strings: $a = "A\n" $b = { 4d 5a } condition: $a and filesize > 1KB + 0x10 }
+rule a { strings: $a = "\x41\n" $b = { 4D 5A } condition: $a and filesize > 1024 + 16 }
+rule a { strings: $a = "a\n" $b = { 4D 5A } condition: $a and filesize > 1024 + 16 }
+"#,
+        );
+        let options = StructuralOptions { normalize_literals: true };
+        assert!(!rules[0].structurally_eq(&rules[1]));
+        assert!(rules[0].structurally_eq_with(&rules[1], &options));
+        assert_eq!(
+            rules[0].structural_hash_with(&options),
+            rules[1].structural_hash_with(&options)
+        );
+        assert!(!rules[0].structurally_eq_with(&rules[2], &options));
+    }
+}
@@ -4,35 +4,170 @@ use text_size::{TextRange, TextSize};
 
 /// Represents an error that can happen during parsing or lexing
 /// This can be also used in further AST validations to throw another error
-/// Each error has a message and a range
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct SyntaxError(String, TextRange);
+/// Each error has a message, a range and optionally suggestions how to fix it
+/// and a related range, for example the first definition of a duplicated name
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SyntaxError {
+    message: String,
+    range: TextRange,
+    severity: Severity,
+    related: Option<TextRange>,
+    suggestions: Vec<Suggestion>,
+}
+
+/// How serious the reported problem is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The file is rejected by YARA
+    #[default]
+    Error,
+    /// The file is accepted, but it is likely a mistake
+    Warning,
+}
 
 impl SyntaxError {
     /// Create a new error with a message and a range
     pub fn new(message: impl Into<String>, range: TextRange) -> Self {
-        Self(message.into(), range)
+        Self {
+            message: message.into(),
+            range,
+            severity: Severity::Error,
+            related: None,
+            suggestions: Vec::new(),
+        }
     }
 
     /// Create a new error with a message and an offset
     pub fn new_at_offset(message: impl Into<String>, offset: TextSize) -> Self {
-        Self(message.into(), TextRange::empty(offset))
+        Self::new(message, TextRange::empty(offset))
+    }
+
+    /// Get the message
+    pub fn message(&self) -> &str {
+        &self.message
     }
 
     /// Get the range
     pub fn range(&self) -> TextRange {
-        self.1
+        self.range
+    }
+
+    /// Get the severity, parse errors are always `Severity::Error`
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Get the related range
+    pub fn related(&self) -> Option<TextRange> {
+        self.related
+    }
+
+    /// Get the suggested fixes, the most likely ones first
+    pub fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
     }
 
     /// Connect range to the error
     pub fn with_range(mut self, range: TextRange) -> Self {
-        self.1 = range;
+        self.range = range;
+        self
+    }
+
+    /// Change the severity of the error
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
         self
     }
+
+    /// Connect a related range to the error
+    pub fn with_related(mut self, range: TextRange) -> Self {
+        self.related = Some(range);
+        self
+    }
+
+    /// Add a suggested fix to the error
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
+}
+
+// Warnings, related ranges and suggestions are printed only when there are some,
+// so plain errors look like a tuple of message and range
+impl fmt::Debug for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut tuple = f.debug_tuple("SyntaxError");
+        tuple.field(&self.message).field(&self.range);
+        if self.severity != Severity::Error {
+            tuple.field(&self.severity);
+        }
+        if let Some(related) = &self.related {
+            tuple.field(related);
+        }
+        if !self.suggestions.is_empty() {
+            tuple.field(&self.suggestions);
+        }
+        tuple.finish()
+    }
 }
 
 impl fmt::Display for SyntaxError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.fmt(f)
+        self.message.fmt(f)
+    }
+}
+
+/// A ready-to-apply fix for an error
+/// It replaces the text in `range` with `replacement`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Suggestion {
+    label: String,
+    range: TextRange,
+    replacement: String,
+}
+
+impl Suggestion {
+    pub fn new(label: impl Into<String>, range: TextRange, replacement: impl Into<String>) -> Self {
+        Self { label: label.into(), range, replacement: replacement.into() }
+    }
+
+    /// Human readable description of the fix
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Range of the text that is replaced
+    pub fn range(&self) -> TextRange {
+        self.range
+    }
+
+    /// The new text
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// Apply the fix to the text the error was reported for
+    pub fn apply(&self, text: &str) -> String {
+        let mut res = text.to_string();
+        res.replace_range(std::ops::Range::<usize>::from(self.range), &self.replacement);
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SourceFile;
+
+    #[test]
+    fn apply_suggestions() {
+        let mut text = "rule a { strings: $abc = \"foo\" nocas condtion: $abc }".to_string();
+        loop {
+            let parse = SourceFile::parse(&text);
+            match parse.errors().first() {
+                Some(error) => text = error.suggestions()[0].apply(&text),
+                None => break,
+            }
+        }
+        assert_eq!(text, "rule a { strings: $abc = \"foo\" nocase condition: $abc }");
     }
 }
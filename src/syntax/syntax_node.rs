@@ -6,9 +6,10 @@
 //! This is just a wrapper around `rowan` crate API
 
 use rowan_test::{GreenNodeBuilder, Language};
-use text_size::TextSize;
+use text_size::TextRange;
 
-use crate::parser::{self, syntax_kind::SyntaxKind};
+use crate::parser::syntax_kind::SyntaxKind;
+use crate::syntax::events::EventSink;
 use crate::SyntaxError;
 
 pub(crate) use rowan_test::GreenNode;
@@ -67,9 +68,23 @@ impl SyntaxTreeBuilder {
     pub fn finish_node(&mut self) {
         self.inner.finish_node()
     }
+}
+
+impl EventSink for SyntaxTreeBuilder {
+    fn start_node(&mut self, kind: SyntaxKind) {
+        SyntaxTreeBuilder::start_node(self, kind)
+    }
+
+    fn token(&mut self, kind: SyntaxKind, _range: TextRange, text: &str) {
+        SyntaxTreeBuilder::token(self, kind, text)
+    }
+
+    fn finish_node(&mut self) {
+        SyntaxTreeBuilder::finish_node(self)
+    }
 
     /// Add a new syntax error to the list of errors
-    pub fn error(&mut self, error: parser::ParseError, text_pos: TextSize) {
-        self.errors.push(SyntaxError::new_at_offset(error.0, text_pos))
+    fn error(&mut self, error: SyntaxError) {
+        self.errors.push(error)
     }
 }
@@ -167,8 +167,30 @@ pub(crate) struct AstNodeSrc {
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) enum Field {
-    Token(String),
-    Node { name: String, ty: String, cardinality: Cardinality },
+    /// Token with an optional label, labels only select shared traits
+    /// and the accessor is still named after the token
+    Token {
+        name: String,
+        label: Option<String>,
+    },
+    Node {
+        name: String,
+        ty: String,
+        cardinality: Cardinality,
+    },
+    /// `lhs` or `rhs` token that is found by its position around the separator,
+    /// for example `lhs:'int_lit' '-' rhs:'int_lit'`
+    Positional {
+        label: String,
+        token: String,
+        separator: Option<Separator>,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum Separator {
+    Before(String),
+    After(String),
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -13,7 +13,7 @@ use quote::{format_ident, quote};
 use ungrammar::{Grammar, Rule};
 
 use crate::syntax::tests::ast_src::{
-    AstEnumSrc, AstNodeSrc, AstSrc, Cardinality, Field, KindsSrc, KINDS_SRC,
+    AstEnumSrc, AstNodeSrc, AstSrc, Cardinality, Field, KindsSrc, Separator, KINDS_SRC,
 };
 
 use crate::syntax::tests::tools::{
@@ -41,6 +41,10 @@ fn sourcegen_ast() {
     let ast_nodes = generate_nodes(KINDS_SRC, &ast);
     let ast_nodes_file = project_root().join("src/syntax/ast/generated/nodes.rs");
     ensure_file_contents(ast_nodes_file.as_path(), &ast_nodes);
+
+    let ast_visit = generate_visit(&ast);
+    let ast_visit_file = project_root().join("src/syntax/ast/generated/visit.rs");
+    ensure_file_contents(ast_visit_file.as_path(), &ast_visit);
 }
 
 fn generate_tokens(grammar: &AstSrc) -> String {
@@ -96,7 +100,25 @@ fn generate_nodes(kinds: KindsSrc<'_>, grammar: &AstSrc) -> String {
                 let method_name = field.method_name();
                 let ty = field.ty();
 
-                if field.is_many() {
+                if let Field::Positional { token, separator, .. } = field {
+                    let kind = token_kind(token);
+                    let body = match separator {
+                        Some(Separator::Before(separator)) => {
+                            let separator = token_kind(separator);
+                            quote!(support::token_before(&self.syntax, #kind, #separator))
+                        }
+                        Some(Separator::After(separator)) => {
+                            let separator = token_kind(separator);
+                            quote!(support::token_after(&self.syntax, #kind, #separator))
+                        }
+                        None => quote!(support::token(&self.syntax, #kind)),
+                    };
+                    quote! {
+                        pub fn #method_name(&self) -> Option<#ty> {
+                            #body
+                        }
+                    }
+                } else if field.is_many() {
                     quote! {
                         pub fn #method_name(&self) -> AstChildren<#ty> {
                             support::children(&self.syntax)
@@ -315,6 +337,95 @@ fn generate_nodes(kinds: KindsSrc<'_>, grammar: &AstSrc) -> String {
     res.replace("#[derive", "\n#[derive")
 }
 
+/// Generate `Visitor` and `Fold` traits with a method for each node type
+/// and functions that dispatch an untyped node to the method of its kind
+fn generate_visit(grammar: &AstSrc) -> String {
+    let names: Vec<_> = grammar.nodes.iter().map(|node| format_ident!("{}", node.name)).collect();
+    let kinds: Vec<_> = grammar
+        .nodes
+        .iter()
+        .map(|node| format_ident!("{}", to_upper_snake_case(&node.name)))
+        .collect();
+    let visit_methods: Vec<_> = grammar
+        .nodes
+        .iter()
+        .map(|node| format_ident!("visit_{}", to_lower_snake_case(&node.name)))
+        .collect();
+    let fold_methods: Vec<_> = grammar
+        .nodes
+        .iter()
+        .map(|node| format_ident!("fold_{}", to_lower_snake_case(&node.name)))
+        .collect();
+
+    let ast = quote! {
+        use crate::{
+            SyntaxNode, SyntaxToken, SyntaxKind::*,
+            syntax::ast::{*, walk::{fold_children, walk_node}},
+        };
+
+        /// Read-only traversal of the typed AST
+        /// Every method by default visits all children of the node in source order,
+        /// overridden methods can call `walk_node` to continue the traversal.
+        pub trait Visitor {
+            /// Called for every token, including trivia
+            fn visit_token(&mut self, token: &SyntaxToken) {
+                let _ = token;
+            }
+            /// Called for nodes without a typed representation, like `ERROR`
+            fn visit_untyped(&mut self, node: &SyntaxNode) {
+                walk_node(self, node)
+            }
+            #(
+                fn #visit_methods(&mut self, node: &#names) {
+                    walk_node(self, node.syntax())
+                }
+            )*
+        }
+
+        /// Call the `Visitor` method for the kind of the node
+        pub fn visit_node<V: Visitor + ?Sized>(visitor: &mut V, node: &SyntaxNode) {
+            match node.kind() {
+                #(
+                    #kinds => visitor.#visit_methods(&#names { syntax: node.clone() }),
+                )*
+                _ => visitor.visit_untyped(node),
+            }
+        }
+
+        /// Rewriting traversal of the typed AST
+        /// Every method by default folds all children of the node and rebuilds it
+        /// if any of them changed. Returning `None` removes the node from its parent.
+        /// Rebuilt nodes are detached from the original tree.
+        pub trait Fold {
+            /// Called for every token, including trivia
+            fn fold_token(&mut self, token: SyntaxToken) -> Option<SyntaxToken> {
+                Some(token)
+            }
+            /// Called for nodes without a typed representation, like `ERROR`
+            fn fold_untyped(&mut self, node: SyntaxNode) -> Option<SyntaxNode> {
+                Some(fold_children(self, node))
+            }
+            #(
+                fn #fold_methods(&mut self, node: #names) -> Option<#names> {
+                    Some(#names { syntax: fold_children(self, node.syntax) })
+                }
+            )*
+        }
+
+        /// Call the `Fold` method for the kind of the node
+        pub fn fold_node<F: Fold + ?Sized>(folder: &mut F, node: SyntaxNode) -> Option<SyntaxNode> {
+            match node.kind() {
+                #(
+                    #kinds => folder.#fold_methods(#names { syntax: node }).map(|it| it.syntax),
+                )*
+                _ => folder.fold_untyped(node),
+            }
+        }
+    };
+
+    add_preamble("sourcegen_ast", reformat(ast.to_string()))
+}
+
 fn write_doc_comment(contents: &[String], dest: &mut String) {
     for line in contents {
         writeln!(dest, "///{}", line).unwrap();
@@ -436,21 +547,26 @@ fn generate_syntax_kinds(grammar: KindsSrc<'_>) -> String {
 }
 
 impl Field {
+    /// Name used to match the field with shared traits
+    fn key(&self) -> &str {
+        match self {
+            Field::Token { label, .. } => label.as_deref().unwrap_or_default(),
+            Field::Positional { label, .. } => label,
+            Field::Node { name, .. } => name,
+        }
+    }
     fn is_many(&self) -> bool {
         matches!(self, Field::Node { cardinality: Cardinality::Many, .. })
     }
     fn token_kind(&self) -> Option<proc_macro2::TokenStream> {
         match self {
-            Field::Token(token) => {
-                let token: proc_macro2::TokenStream = token.parse().unwrap();
-                Some(quote! { T![#token] })
-            }
+            Field::Token { name, .. } => Some(token_kind(name)),
             _ => None,
         }
     }
     fn method_name(&self) -> proc_macro2::Ident {
         match self {
-            Field::Token(name) => {
+            Field::Token { name, .. } => {
                 let name = match name.as_str() {
                     "'{'" => "l_brace",
                     "'}'" => "r_brace",
@@ -484,6 +600,7 @@ impl Field {
                 };
                 format_ident!("{}_token", name)
             }
+            Field::Positional { label, .. } => format_ident!("{}", label),
             Field::Node { name, .. } => {
                 if name == "type" {
                     format_ident!("ty")
@@ -495,12 +612,17 @@ impl Field {
     }
     fn ty(&self) -> proc_macro2::Ident {
         match self {
-            Field::Token(_) => format_ident!("SyntaxToken"),
+            Field::Token { .. } | Field::Positional { .. } => format_ident!("SyntaxToken"),
             Field::Node { ty, .. } => format_ident!("{}", ty),
         }
     }
 }
 
+fn token_kind(token: &str) -> proc_macro2::TokenStream {
+    let token: proc_macro2::TokenStream = token.parse().unwrap();
+    quote! { T![#token] }
+}
+
 fn lower(grammar: &Grammar) -> AstSrc {
     let mut res = AstSrc {
         tokens: "Whitespace Comment StringLit IntLit BoolLit FloatLit Variable"
@@ -563,14 +685,13 @@ fn lower_rule(acc: &mut Vec<Field>, grammar: &Grammar, label: Option<&String>, r
             acc.push(field);
         }
         Rule::Token(token) => {
-            assert!(label.is_none());
             let mut name = grammar[*token].name.clone();
             // add support for numbers
             if name != "number" && name != "string" {
                 if "[](){}".contains(&name) {
                     name = format!("'{}'", name);
                 }
-                let field = Field::Token(name);
+                let field = Field::Token { name, label: label.cloned() };
                 acc.push(field);
             }
         }
@@ -587,13 +708,28 @@ fn lower_rule(acc: &mut Vec<Field>, grammar: &Grammar, label: Option<&String>, r
         }
         Rule::Labeled { label: l, rule } => {
             assert!(label.is_none());
+            if let Some(field) = lower_positional(grammar, &[], 0, l, rule) {
+                acc.push(field);
+                return;
+            }
             let manually_implemented = matches!(l.as_str(), "lhs" | "rhs" | "op" | "value");
             if manually_implemented {
                 return;
             }
             lower_rule(acc, grammar, Some(l), rule);
         }
-        Rule::Seq(rules) | Rule::Alt(rules) => {
+        Rule::Seq(rules) => {
+            for (i, rule) in rules.iter().enumerate() {
+                if let Rule::Labeled { label: l, rule: inner } = rule {
+                    if let Some(field) = lower_positional(grammar, rules, i, l, inner) {
+                        acc.push(field);
+                        continue;
+                    }
+                }
+                lower_rule(acc, grammar, label, rule)
+            }
+        }
+        Rule::Alt(rules) => {
             for rule in rules {
                 lower_rule(acc, grammar, label, rule)
             }
@@ -602,6 +738,37 @@ fn lower_rule(acc: &mut Vec<Field>, grammar: &Grammar, label: Option<&String>, r
     }
 }
 
+/// Lower `lhs` or `rhs` labelled token at `index` of the sequence `seq`
+/// The separator is the unlabelled token right after or before it
+fn lower_positional(
+    grammar: &Grammar,
+    seq: &[Rule],
+    index: usize,
+    label: &str,
+    rule: &Rule,
+) -> Option<Field> {
+    fn token_name(grammar: &Grammar, rule: &Rule) -> Option<String> {
+        match rule {
+            Rule::Token(token) => Some(grammar[*token].name.clone()),
+            Rule::Opt(rule) => token_name(grammar, rule),
+            _ => None,
+        }
+    }
+
+    if !matches!(label, "lhs" | "rhs") {
+        return None;
+    }
+    let token = token_name(grammar, rule)?;
+    let next = seq.get(index + 1).and_then(|it| token_name(grammar, it));
+    let prev = index.checked_sub(1).and_then(|it| token_name(grammar, &seq[it]));
+    let separator = match (next, prev) {
+        (Some(next), _) => Some(Separator::Before(next)),
+        (None, Some(prev)) => Some(Separator::After(prev)),
+        (None, None) => None,
+    };
+    Some(Field::Positional { label: label.to_string(), token, separator })
+}
+
 fn lower_comma_list(
     acc: &mut Vec<Field>,
     grammar: &Grammar,
@@ -637,11 +804,23 @@ fn lower_comma_list(
 
 fn extract_struct_traits(ast: &mut AstSrc) {
     let nodes_with_comments = ["SourceFile", "Rule", "BlockExpr", "Strings", "Condition"];
+    // Node implements the trait if it has any of the fields,
+    // labels are used for token fields and names of accessors for node fields
+    let traits: &[(&str, &[&str])] = &[
+        ("HasName", &["name"]),
+        ("HasModifiers", &["modifiers", "pattern_mods"]),
+        ("HasTags", &["tags"]),
+    ];
 
     for node in &mut ast.nodes {
         if nodes_with_comments.contains(&&*node.name) {
             node.traits.push("HasComments".into());
         }
+        for (trait_name, fields) in traits {
+            if node.fields.iter().any(|field| fields.contains(&field.key())) {
+                node.traits.push(trait_name.to_string());
+            }
+        }
     }
 }
 
@@ -652,7 +831,8 @@ fn deduplicate_fields(ast: &mut AstSrc) {
             for j in 0..i {
                 let f1 = &node.fields[i];
                 let f2 = &node.fields[j];
-                if f1 == f2 {
+                // The same label can be in multiple alternatives, the first one wins
+                if f1 == f2 || f1.method_name() == f2.method_name() {
                     node.fields.remove(i);
                     continue 'outer;
                 }
@@ -42,21 +42,26 @@ impl<'t> TokenSource for TextTokenSource<'t> {
             .map(|(token, offset)| &self.text[TextRange::at(*offset, token.len)] == kw)
             .unwrap_or(false)
     }
+
+    /// Returns the text of the current token, empty at the end of input
+    fn current_text(&self) -> &str {
+        self.token_offset_pairs
+            .get(self.curr.1)
+            .map(|(token, offset)| &self.text[TextRange::at(*offset, token.len)])
+            .unwrap_or("")
+    }
 }
 
 /// Create a token from a position
 fn mk_token(pos: usize, token_offset_pairs: &[(Token, TextSize)]) -> parser::Token {
-    let (kind, is_jointed_to_next) = match token_offset_pairs.get(pos) {
-        Some((token, offset)) => (
-            token.kind,
-            token_offset_pairs
-                .get(pos + 1)
-                .map(|(_, next_offset)| offset + token.len == *next_offset)
-                .unwrap_or(false),
-        ),
-        None => (EOF, false),
-    };
-    parser::Token { kind, is_jointed_to_next }
+    let is_jointed =
+        |pos: usize| match (token_offset_pairs.get(pos), token_offset_pairs.get(pos + 1)) {
+            (Some((token, offset)), Some((_, next_offset))) => offset + token.len == *next_offset,
+            _ => false,
+        };
+    let kind = token_offset_pairs.get(pos).map_or(EOF, |(token, _)| token.kind);
+    let is_jointed_to_prev = pos.checked_sub(1).is_some_and(is_jointed);
+    parser::Token { kind, is_jointed_to_prev, is_jointed_to_next: is_jointed(pos) }
 }
 
 /// Generate token-offset pairs
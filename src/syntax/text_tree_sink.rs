@@ -4,18 +4,22 @@ use text_size::{TextRange, TextSize};
 use crate::{
     lexer::Token,
     parser::{ParseError, SyntaxKind, TreeSink},
-    syntax::{syntax_error::SyntaxError, syntax_node::GreenNode, syntax_node::SyntaxTreeBuilder},
+    syntax::{
+        events::EventSink,
+        syntax_error::{Suggestion, SyntaxError},
+    },
 };
 
 /// Used to connect parser and specific SyntaxTree representation
 /// It also handles attaching trivia (whitespaces and comments) to the nodes
+/// The resulting text-level events are passed to the `EventSink`
 pub(crate) struct TextTreeSink<'a> {
     text: &'a str,
     tokens: &'a [Token],
     text_pos: TextSize,
     token_pos: usize,
     state: State,
-    inner: SyntaxTreeBuilder,
+    inner: &'a mut dyn EventSink,
 }
 
 enum State {
@@ -70,7 +74,7 @@ impl<'a> TreeSink for TextTreeSink<'a> {
                 trivia_end = next_end;
                 (it.kind, &self.text[range])
             });
-            n_attached_trivias(kind, leading_trivias)
+            n_attached_trivias(kind, leading_trivias, self.token_pos == 0)
         };
         self.eat_n_trivias(n_trivias - n_attached_trivias);
         self.inner.start_node(kind);
@@ -89,23 +93,28 @@ impl<'a> TreeSink for TextTreeSink<'a> {
     }
 
     fn error(&mut self, error: ParseError) {
-        self.inner.error(error, self.text_pos)
+        let mut syntax_error = SyntaxError::new_at_offset(error.message, self.text_pos);
+        if !error.suggestions.is_empty() {
+            let range = self.current_token_range();
+            for suggestion in error.suggestions {
+                syntax_error = syntax_error.with_suggestion(Suggestion::new(
+                    suggestion.label,
+                    range,
+                    suggestion.replacement,
+                ));
+            }
+        }
+        self.inner.error(syntax_error)
     }
 }
 
 impl<'a> TextTreeSink<'a> {
-    pub(crate) fn new(text: &'a str, tokens: &'a [Token]) -> Self {
-        Self {
-            text,
-            tokens,
-            text_pos: 0.into(),
-            token_pos: 0,
-            state: State::PendingStart,
-            inner: SyntaxTreeBuilder::default(),
-        }
+    pub(crate) fn new(text: &'a str, tokens: &'a [Token], inner: &'a mut dyn EventSink) -> Self {
+        Self { text, tokens, text_pos: 0.into(), token_pos: 0, state: State::PendingStart, inner }
     }
 
-    pub(crate) fn finish(mut self) -> (GreenNode, Vec<SyntaxError>) {
+    /// Finish the root node and return the underlying sink
+    pub(crate) fn finish(mut self) -> &'a mut dyn EventSink {
         match mem::replace(&mut self.state, State::Normal) {
             State::PendingFinish => {
                 self.eat_trivias();
@@ -114,7 +123,19 @@ impl<'a> TextTreeSink<'a> {
             State::PendingStart | State::Normal => unreachable!(),
         }
 
-        self.inner.finish_raw()
+        self.inner
+    }
+
+    /// Range of the next non-trivia token, the one the parser is currently at
+    fn current_token_range(&self) -> TextRange {
+        let mut start = self.text_pos;
+        for token in &self.tokens[self.token_pos..] {
+            if !token.kind.is_trivia() {
+                return TextRange::at(start, token.len);
+            }
+            start += token.len;
+        }
+        TextRange::empty(start)
     }
 
     /// Consumes trivias until the next non-trivia token
@@ -142,29 +163,46 @@ impl<'a> TextTreeSink<'a> {
         let text = &self.text[range];
         self.text_pos += len;
         self.token_pos += n_tokens;
-        self.inner.token(kind, text);
+        self.inner.token(kind, range, text);
     }
 }
 
 /// Returns the number of attached trivias for the given node kind
 /// Trivias are attached to the node if they are directly before the node
-/// and there is no empty line between the trivia and the node
+/// and there is no empty line between the trivia and the node.
+/// A comment that ends the line of a previous token belongs to that token,
+/// so it is never attached to the following node.
+///
+/// `trivias` are in reverse order, `at_file_start` tells if there
+/// is no token before them.
 fn n_attached_trivias<'a>(
     kind: SyntaxKind,
     trivias: impl Iterator<Item = (SyntaxKind, &'a str)>,
+    at_file_start: bool,
 ) -> usize {
     match kind {
         // Nodes that are supported to have attached trivias
         SyntaxKind::RULE | SyntaxKind::BLOCK_EXPR | SyntaxKind::STRINGS | SyntaxKind::CONDITION => {
+            let trivias: Vec<_> = trivias.collect();
             let mut res = 0;
-            let trivias = trivias.enumerate().peekable();
 
-            for (i, (kind, text)) in trivias {
+            for (i, (kind, text)) in trivias.iter().enumerate() {
                 match kind {
-                    SyntaxKind::WHITESPACE if text.contains("\n\n") => {
+                    SyntaxKind::WHITESPACE if is_empty_line(text) => {
                         break;
                     }
                     SyntaxKind::COMMENT => {
+                        let starts_line = match trivias.get(i + 1) {
+                            Some((SyntaxKind::WHITESPACE, text)) if text.contains('\n') => true,
+                            Some((SyntaxKind::WHITESPACE, _)) => {
+                                i + 2 == trivias.len() && at_file_start
+                            }
+                            Some(_) => false,
+                            None => at_file_start,
+                        };
+                        if !starts_line {
+                            break;
+                        }
                         res = i + 1;
                     }
                     _ => (),
@@ -175,3 +213,8 @@ fn n_attached_trivias<'a>(
         _ => 0,
     }
 }
+
+/// Check if the whitespace contains an empty line
+fn is_empty_line(text: &str) -> bool {
+    text.matches('\n').count() > 1
+}
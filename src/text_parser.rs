@@ -0,0 +1,113 @@
+//! Building blocks of the parsers of small text formats like the query language
+//! in `query` and the schema format in `schema`
+//!
+//! A format is first split into tokens by its own tokenizer and the tokens
+//! are then parsed by methods implemented on `Parser<Token>` with its `Token` type.
+
+use std::{error::Error, fmt, iter::Peekable, str::CharIndices};
+
+use text_size::{TextRange, TextSize};
+
+/// Error in the text of a small format like a query or a schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextError {
+    message: String,
+    range: TextRange,
+}
+
+impl TextError {
+    pub(crate) fn new(message: impl Into<String>, range: TextRange) -> Self {
+        Self { message: message.into(), range }
+    }
+
+    /// Get the message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Get the range in the parsed text
+    pub fn range(&self) -> TextRange {
+        self.range
+    }
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {:?}", self.message, self.range)
+    }
+}
+
+impl Error for TextError {}
+
+/// Contents of a string literal whose opening `"` at `start` was already consumed
+///
+/// `\n` and `\t` are escapes, any other character after `\` stands for itself.
+pub(crate) fn string_lit(
+    chars: &mut Peekable<CharIndices>,
+    start: usize,
+    src: &str,
+) -> Result<String, TextError> {
+    let mut text = String::new();
+    loop {
+        let c = match chars.next() {
+            Some((_, '\\')) => chars.next().map(|(_, c)| match c {
+                'n' => '\n',
+                't' => '\t',
+                c => c,
+            }),
+            Some((_, '"')) => return Ok(text),
+            next => next.map(|(_, c)| c),
+        };
+        match c {
+            Some(c) => text.push(c),
+            None => {
+                let range = TextRange::new(offset(start), TextSize::of(src));
+                return Err(TextError::new("unterminated string", range));
+            }
+        }
+    }
+}
+
+pub(crate) fn offset(offset: usize) -> TextSize {
+    TextSize::from(offset as u32)
+}
+
+/// Position in a list of tokens with their ranges
+pub(crate) struct Parser<T> {
+    pub(crate) tokens: Vec<(T, TextRange)>,
+    pub(crate) pos: usize,
+    /// End of the text, the range of errors at the end of the input
+    pub(crate) end: TextSize,
+}
+
+impl<T: Clone> Parser<T> {
+    pub(crate) fn new(tokens: Vec<(T, TextRange)>, src: &str) -> Self {
+        Self { tokens, pos: 0, end: TextSize::of(src) }
+    }
+
+    pub(crate) fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    pub(crate) fn peek(&self) -> Option<&T> {
+        self.peek_nth(0)
+    }
+
+    pub(crate) fn peek_nth(&self, n: usize) -> Option<&T> {
+        self.tokens.get(self.pos + n).map(|(token, _)| token)
+    }
+
+    pub(crate) fn range(&self) -> TextRange {
+        self.tokens.get(self.pos).map_or(TextRange::empty(self.end), |(_, range)| *range)
+    }
+
+    pub(crate) fn bump(&mut self) -> Option<T> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token
+    }
+
+    pub(crate) fn error<R>(&self, message: impl Into<String>) -> Result<R, TextError> {
+        Err(TextError::new(message, self.range()))
+    }
+}